@@ -1,94 +1,31 @@
-use shared::dprintln;
+use anyhow::Result;
 
-const INPUT: &str = include_str!("../input");
+mod map;
 
-fn main() {
-    let mut rows = INPUT.lines().map(|_| 2).collect::<Vec<_>>();
-    let mut columns = INPUT
-        .lines()
-        .next()
-        .unwrap()
-        .chars()
-        .map(|_| 2)
-        .collect::<Vec<_>>();
-    let mut galaxies = vec![];
+const INPUT: &str = include_str!("../input");
 
-    for (i, line) in INPUT.lines().enumerate() {
-        for (j, _) in line.chars().enumerate().filter(|&(_, c)| c == '#') {
-            rows[i] = 1;
-            columns[j] = 1;
-            galaxies.push((i, j));
-        }
-    }
+fn main() -> Result<()> {
+    let map = INPUT.parse::<map::GalaxyMap>()?;
 
-    let mut sum = 0;
+    let galaxies = map.expand(2);
 
-    for (g1, g2) in galaxies
-        .iter()
-        .enumerate()
-        .flat_map(|(i, g1)| galaxies[i + 1..].iter().map(|g2| (*g1, *g2)))
-    {
-        let i = ordered(g1.0, g2.0);
-        let j = ordered(g1.1, g2.1);
+    // `day11 nearest [k]` lists the k closest galaxies to each galaxy instead
+    // of solving
+    let mut args = std::env::args().skip(1);
 
-        let i_distance: u64 = rows[i.0..i.1].iter().sum();
-        let j_distance: u64 = columns[j.0..j.1].iter().sum();
+    if args.next().as_deref() == Some("nearest") {
+        let k = args.next().map(|k| k.parse()).transpose()?.unwrap_or(1);
 
-        let num = j_distance + i_distance;
-        dprintln!(
-            "{g1:?}, {g2:?}: {}|{:?}|{:?}",
-            num,
-            &rows[i.0..i.1],
-            &columns[j.0..j.1]
-        );
+        for (i, nearest) in galaxies.nearest(k).iter().enumerate() {
+            println!("{}: {nearest:?}", i + 1);
+        }
 
-        sum += num;
+        return Ok(());
     }
 
-    println!("part1: {sum}");
-
-    // part 2
-    let distance = 1_000_000;
-    columns
-        .iter_mut()
-        .filter(|i| **i == 2)
-        .for_each(|i| *i = distance);
-
-    rows.iter_mut()
-        .filter(|i| **i == 2)
-        .for_each(|i| *i = distance);
-
-    let mut sum = 0;
+    println!("part1: {}", galaxies.pairwise_sum());
 
-    for (g1, g2) in galaxies
-        .iter()
-        .enumerate()
-        .flat_map(|(i, g1)| galaxies[i + 1..].iter().map(|g2| (*g1, *g2)))
-    {
-        let i = ordered(g1.0, g2.0);
-        let j = ordered(g1.1, g2.1);
+    println!("part2: {}", map.expand(1_000_000).pairwise_sum());
 
-        let i_distance: u64 = rows[i.0..i.1].iter().sum();
-        let j_distance: u64 = columns[j.0..j.1].iter().sum();
-
-        let num = j_distance + i_distance;
-        dprintln!(
-            "{g1:?}, {g2:?}: {}|{:?}|{:?}",
-            num,
-            &rows[i.0..i.1],
-            &columns[j.0..j.1]
-        );
-
-        sum += num;
-    }
-
-    println!("part2: {sum}");
-}
-
-fn ordered<T: PartialOrd<T>>(a: T, b: T) -> (T, T) {
-    if a < b {
-        (a, b)
-    } else {
-        (b, a)
-    }
+    Ok(())
 }
@@ -0,0 +1,183 @@
+use std::str::FromStr;
+
+use anyhow::{bail, Error, Result};
+
+#[derive(Debug)]
+pub struct GalaxyMap {
+    galaxies: Vec<(usize, usize)>,
+    empty_rows: Vec<bool>,
+    empty_columns: Vec<bool>,
+}
+
+impl GalaxyMap {
+    /// places every galaxy in the universe after each empty row and column has
+    /// been replaced by `factor` empty rows or columns
+    pub fn expand(&self, factor: u64) -> Galaxies {
+        let rows = expansion_offsets(&self.empty_rows, factor);
+        let columns = expansion_offsets(&self.empty_columns, factor);
+
+        Galaxies {
+            positions: self
+                .galaxies
+                .iter()
+                .map(|&(i, j)| (rows[i], columns[j]))
+                .collect(),
+        }
+    }
+}
+
+/// the expanded coordinate of every index along one axis
+fn expansion_offsets(empty: &[bool], factor: u64) -> Vec<u64> {
+    empty
+        .iter()
+        .scan(0, |pos, &e| {
+            let curr = *pos;
+            *pos += if e { factor } else { 1 };
+            Some(curr)
+        })
+        .collect()
+}
+
+impl FromStr for GalaxyMap {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let width = s.lines().next().map_or(0, str::len);
+
+        let mut empty_rows = vec![true; s.lines().count()];
+        let mut empty_columns = vec![true; width];
+        let mut galaxies = vec![];
+
+        for (i, line) in s.lines().enumerate() {
+            if line.len() != width {
+                bail!("line {i} has length {} instead of {width}", line.len());
+            }
+
+            for (j, c) in line.chars().enumerate() {
+                match c {
+                    '#' => {
+                        empty_rows[i] = false;
+                        empty_columns[j] = false;
+                        galaxies.push((i, j));
+                    }
+                    '.' => (),
+                    _ => bail!("the character {c} is not valid in a galaxy map"),
+                }
+            }
+        }
+
+        Ok(Self {
+            galaxies,
+            empty_rows,
+            empty_columns,
+        })
+    }
+}
+
+/// galaxy positions in an expanded universe, numbered from 1 in reading order
+/// like in the puzzle text
+#[derive(Debug)]
+pub struct Galaxies {
+    positions: Vec<(u64, u64)>,
+}
+
+impl Galaxies {
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn distance(&self, a: usize, b: usize) -> Option<u64> {
+        let a = self.positions.get(a.checked_sub(1)?)?;
+        let b = self.positions.get(b.checked_sub(1)?)?;
+
+        Some(a.0.abs_diff(b.0) + a.1.abs_diff(b.1))
+    }
+
+    /// the `k` closest other galaxies to each galaxy, as `(galaxy, distance)`
+    /// pairs sorted by distance then by galaxy number
+    pub fn nearest(&self, k: usize) -> Vec<Vec<(usize, u64)>> {
+        (1..=self.len())
+            .map(|a| {
+                let mut others = (1..=self.len())
+                    .filter(|&b| b != a)
+                    .map(|b| (b, self.distance(a, b).unwrap()))
+                    .collect::<Vec<_>>();
+
+                others.sort_unstable_by_key(|&(b, d)| (d, b));
+                others.truncate(k);
+                others
+            })
+            .collect()
+    }
+
+    /// the sum of the distances between every pair of galaxies
+    ///
+    /// the manhattan distance splits into the two axes, and along one sorted
+    /// axis the `n`th coordinate is `n * x - (sum of the previous coordinates)`
+    /// away from all the ones before it
+    pub fn pairwise_sum(&self) -> u64 {
+        let mut rows = self.positions.iter().map(|p| p.0).collect::<Vec<_>>();
+        let mut columns = self.positions.iter().map(|p| p.1).collect::<Vec<_>>();
+
+        axis_sum(&mut rows) + axis_sum(&mut columns)
+    }
+}
+
+fn axis_sum(coords: &mut [u64]) -> u64 {
+    coords.sort_unstable();
+
+    coords
+        .iter()
+        .enumerate()
+        .fold((0, 0), |(sum, prefix), (n, &x)| {
+            (sum + n as u64 * x - prefix, prefix + x)
+        })
+        .0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GalaxyMap;
+
+    const SAMPLE: &str = "...#......
+.......#..
+#.........
+..........
+......#...
+.#........
+.........#
+..........
+.......#..
+#...#.....";
+
+    #[test]
+    fn pairwise_sum() {
+        let map = SAMPLE.parse::<GalaxyMap>().unwrap();
+
+        assert_eq!(map.expand(2).pairwise_sum(), 374);
+        assert_eq!(map.expand(10).pairwise_sum(), 1030);
+        assert_eq!(map.expand(100).pairwise_sum(), 8410);
+    }
+
+    #[test]
+    fn distance() {
+        let galaxies = SAMPLE.parse::<GalaxyMap>().unwrap().expand(2);
+
+        assert_eq!(galaxies.distance(5, 9), Some(9));
+        assert_eq!(galaxies.distance(1, 7), Some(15));
+        assert_eq!(galaxies.distance(3, 6), Some(17));
+        assert_eq!(galaxies.distance(8, 9), Some(5));
+        assert_eq!(galaxies.distance(0, 9), None);
+        assert_eq!(galaxies.distance(1, 10), None);
+    }
+
+    #[test]
+    fn nearest() {
+        let galaxies = SAMPLE.parse::<GalaxyMap>().unwrap().expand(2);
+        let nearest = galaxies.nearest(2);
+
+        assert_eq!(nearest.len(), 9);
+        assert_eq!(nearest[7], vec![(9, 5), (5, 6)]);
+        assert!(nearest.iter().all(|n| n.len() == 2));
+    }
+}
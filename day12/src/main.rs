@@ -1,10 +1,15 @@
 use std::{collections::HashMap, str::FromStr};
 
-use anyhow::{anyhow, bail, Error, Result};
-use shared::dprintln;
+use anyhow::{anyhow, bail, Result};
+use shared::rng::XorShift;
+
+mod nonogram;
 
 const INPUT: &str = include_str!("../input");
 
+/// how many times part 2 unfolds each row unless told otherwise
+const UNFOLD: usize = 5;
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Springs {
     Y,
@@ -13,6 +18,38 @@ enum Springs {
 }
 
 fn main() -> Result<()> {
+    // `day12 unfold <factor>` unfolds each row factor times for part 2,
+    // `day12 arrangements` lists every arrangement of every row,
+    // `day12 sample [factor]` prints a random arrangement of every unfolded
+    // row, and `day12 <file.non>` solves a nonogram instead of the puzzle
+    // input
+    let mut args = std::env::args().skip(1);
+    let mode = args.next();
+    let listing = matches!(mode.as_deref(), Some("arrangements" | "sample"));
+
+    let factor = match mode.as_deref() {
+        Some("unfold" | "sample") => args.next().map(|f| f.parse()).transpose()?,
+        _ => None,
+    }
+    .unwrap_or(UNFOLD);
+
+    if factor == 0 {
+        bail!("can't unfold a row 0 times");
+    }
+
+    if let Some(path) = mode.as_deref().filter(|&m| !listing && m != "unfold") {
+        let nonogram = std::fs::read_to_string(path)?.parse::<nonogram::Nonogram>()?;
+
+        match nonogram.solve()? {
+            nonogram::Solution::Unique(picture) => println!("unique solution:\n{picture}"),
+            nonogram::Solution::Multiple(a, b) => {
+                println!("multiple solutions, for example:\n{a}\n{b}")
+            }
+        }
+
+        return Ok(());
+    }
+
     let cases = INPUT
         .lines()
         .map(|l| -> Result<_> {
@@ -36,7 +73,7 @@ fn main() -> Result<()> {
                 .try_fold(
                     (vec![], vec![], 0),
                     |(mut unknowns, mut springs, mut num_known), n| -> Result<_> {
-                        let n = n.map_err::<Error, _>(Into::into)?;
+                        let n = n?;
 
                         match n {
                             Springs::M => unknowns.push(springs.len()),
@@ -54,43 +91,82 @@ fn main() -> Result<()> {
         })
         .collect::<Result<Vec<_>, _>>()?;
 
-    let mut sum1 = 0;
-    let mut sum2 = 0;
+    let mut sum1 = 0u128;
+    let mut sum2 = 0u128;
+    let mut rng = XorShift::from_entropy();
 
-    for (unknowns, num_total, num_known, mut springs, chains) in cases {
-        sum1 += SpringSolver::new(springs.clone(), chains.clone()).solve((0, 0, 0));
+    for (unknowns, num_total, num_known, springs, chains) in cases {
+        let mut solver = SpringSolver::new(springs.clone(), chains.clone());
+        let count = solver.count()?;
 
-        springs.push(Springs::M);
-        let mut springs = springs.repeat(5);
-        springs.pop();
+        if mode.as_deref() == Some("arrangements") {
+            println!("{} {chains:?}", springs_to_string(&springs));
 
-        dprintln!(
-            "{:?}|{:?}|{}|{}",
-            springs
-                .iter()
-                .map(|s| match s {
-                    Springs::Y => '#',
-                    Springs::N => '.',
-                    Springs::M => '?',
-                })
-                .collect::<String>(),
-            chains.repeat(5),
-            unknowns.len() * 5 + 4,
-            num_total * 5 - num_known * 5,
-        );
-        sum2 += SpringSolver::new(springs, chains.repeat(5)).solve((0, 0, 0));
+            for arrangement in solver.arrangements() {
+                println!("    {}", springs_to_string(&arrangement));
+            }
+        }
+
+        sum1 = sum1
+            .checked_add(count)
+            .ok_or(anyhow!("the sum of arrangements overflows a u128"))?;
+
+        let (springs, chains) = unfold(&springs, &chains, factor);
+
+        let row = springs_to_string(&springs);
+        let mut solver = SpringSolver::new(springs, chains);
+        sum2 = sum2
+            .checked_add(solver.count()?)
+            .ok_or(anyhow!("the sum of arrangements overflows a u128"))?;
+
+        if mode.as_deref() == Some("sample") {
+            println!(
+                "{row} with {} unknowns and {} damaged springs left to place, like",
+                unknowns.len() * factor + factor - 1,
+                (num_total - num_known) as usize * factor,
+            );
+
+            match solver.sample(&mut rng) {
+                Some(arrangement) => println!("{}", springs_to_string(&arrangement)),
+                None => println!("nothing fits"),
+            }
+        }
+    }
+
+    if listing {
+        return Ok(());
     }
 
     println!("part1: {sum1}");
-    println!("part1: {sum2}");
+    println!("part2: {sum2}");
 
     Ok(())
 }
 
+/// repeats the springs `factor` times with an unknown spring between each
+/// copy, and the chains `factor` times
+fn unfold(springs: &[Springs], chains: &[u32], factor: usize) -> (Vec<Springs>, Vec<u32>) {
+    let mut unfolded = [springs, &[Springs::M]].concat().repeat(factor);
+    unfolded.pop();
+
+    (unfolded, chains.repeat(factor))
+}
+
+fn springs_to_string(springs: &[Springs]) -> String {
+    springs
+        .iter()
+        .map(|s| match s {
+            Springs::Y => '#',
+            Springs::N => '.',
+            Springs::M => '?',
+        })
+        .collect()
+}
+
 struct SpringSolver {
     springs: Vec<Springs>,
     chains: Vec<u32>,
-    map: HashMap<(usize, usize, u32), usize>,
+    map: HashMap<(usize, usize, u32), Option<u128>>,
 }
 
 impl SpringSolver {
@@ -102,47 +178,245 @@ impl SpringSolver {
         }
     }
 
-    fn solve(&mut self, key: (usize, usize, u32)) -> usize {
-        if let Some(v) = self.map.get(&key) {
-            return *v;
-        }
+    /// the number of arrangements of the springs from `key` on, or `None` if
+    /// it doesn't fit in a u128
+    ///
+    /// this works through the states with a stack rather than recursing, so
+    /// that long unfolded rows can't overflow the call stack
+    fn solve(&mut self, key: (usize, usize, u32)) -> Option<u128> {
+        let mut stack = vec![key];
 
-        let (curr_idx, chain_idx, curr_chain) = key;
+        while let Some(&top) = stack.last() {
+            if self.map.contains_key(&top) {
+                stack.pop();
+                continue;
+            }
+
+            let (curr_idx, chain_idx, curr_chain) = top;
+
+            if curr_idx == self.springs.len() {
+                let done = chain_idx == self.chains.len() && curr_chain == 0
+                    || chain_idx + 1 == self.chains.len()
+                        && curr_chain == *self.chains.last().unwrap();
+
+                self.map.insert(top, Some(done as u128));
+                stack.pop();
+                continue;
+            }
+
+            let next = self
+                .choices(curr_idx)
+                .filter_map(|is_block| self.step(top, is_block))
+                .collect::<Vec<_>>();
+
+            let unsolved = next
+                .iter()
+                .filter(|n| !self.map.contains_key(n))
+                .copied()
+                .collect::<Vec<_>>();
 
-        if curr_idx == self.springs.len() {
-            if chain_idx == self.chains.len() && curr_chain == 0
-                || chain_idx == self.chains.len() - 1 && curr_chain == *self.chains.last().unwrap()
-            {
-                return 1;
-            } else {
-                return 0;
+            // every state after this one has to be counted first
+            if !unsolved.is_empty() {
+                stack.extend(unsolved);
+                continue;
             }
+
+            let ans = next
+                .iter()
+                .try_fold(0u128, |ans, next| ans.checked_add(self.map[next]?));
+
+            self.map.insert(top, ans);
+            stack.pop();
         }
 
-        let mut ans = 0;
+        self.map[&key]
+    }
 
-        let next = self.springs[curr_idx];
+    fn count(&mut self) -> Result<u128> {
+        self.solve((0, 0, 0))
+            .ok_or(anyhow!("the number of arrangements overflows a u128"))
+    }
 
-        let states = match next {
+    fn choices(&self, curr_idx: usize) -> impl Iterator<Item = bool> {
+        match self.springs[curr_idx] {
             Springs::Y => vec![true].into_iter(),
             Springs::N => vec![false].into_iter(),
             Springs::M => vec![true, false].into_iter(),
-        };
+        }
+    }
+
+    /// the state after placing a damaged (`is_block`) or operational spring at
+    /// the current index, if that placement can still match the chains
+    fn step(&self, key: (usize, usize, u32), is_block: bool) -> Option<(usize, usize, u32)> {
+        let (curr_idx, chain_idx, curr_chain) = key;
+
+        if is_block {
+            (chain_idx < self.chains.len() && curr_chain < self.chains[chain_idx]).then_some((
+                curr_idx + 1,
+                chain_idx,
+                curr_chain + 1,
+            ))
+        } else if curr_chain != 0 {
+            (chain_idx < self.chains.len() && curr_chain == self.chains[chain_idx]).then_some((
+                curr_idx + 1,
+                chain_idx + 1,
+                0,
+            ))
+        } else {
+            Some((curr_idx + 1, chain_idx, 0))
+        }
+    }
+
+    /// the `n`th valid arrangement, ordered with damaged springs before
+    /// operational ones at each unknown
+    ///
+    /// the memoized counts say how many arrangements are under each choice, so
+    /// this only walks a single path through the row
+    fn nth(&mut self, mut n: u128) -> Option<Vec<Springs>> {
+        let mut key = (0, 0, 0);
+
+        if n >= self.solve(key)? {
+            return None;
+        }
 
-        for is_block in states {
-            if is_block {
-                ans += self.solve((curr_idx + 1, chain_idx, curr_chain + 1))
-            } else if curr_chain != 0 {
-                if chain_idx < self.chains.len() && curr_chain == self.chains[chain_idx] {
-                    ans += self.solve((curr_idx + 1, chain_idx + 1, 0))
+        let mut arrangement = Vec::with_capacity(self.springs.len());
+
+        while key.0 < self.springs.len() {
+            let choices = self.choices(key.0).collect::<Vec<_>>();
+
+            for is_block in choices {
+                let Some(next) = self.step(key, is_block) else {
+                    continue;
+                };
+
+                let count = self.solve(next)?;
+
+                if n < count {
+                    arrangement.push(if is_block { Springs::Y } else { Springs::N });
+                    key = next;
+                    break;
                 }
-            } else {
-                ans += self.solve((curr_idx + 1, chain_idx, 0))
+
+                n -= count;
             }
         }
 
-        self.map.insert(key, ans);
+        Some(arrangement)
+    }
+
+    /// a single arrangement picked at random, which is as good as uniform as
+    /// long as there are far fewer than 2^128 arrangements
+    fn sample(&mut self, rng: &mut XorShift) -> Option<Vec<Springs>> {
+        let count = self.solve((0, 0, 0))?;
+
+        if count == 0 {
+            return None;
+        }
+
+        self.nth(rng.next_u128() % count)
+    }
+
+    /// every valid arrangement, built lazily one at a time
+    fn arrangements(&mut self) -> Arrangements<'_> {
+        let count = self.solve((0, 0, 0)).unwrap_or(u128::MAX);
+
+        Arrangements {
+            solver: self,
+            next: 0,
+            count,
+        }
+    }
+}
+
+struct Arrangements<'a> {
+    solver: &'a mut SpringSolver,
+    next: u128,
+    count: u128,
+}
+
+impl Iterator for Arrangements<'_> {
+    type Item = Vec<Springs>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == self.count {
+            return None;
+        }
+
+        self.next += 1;
+        self.solver.nth(self.next - 1)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = usize::try_from(self.count - self.next);
+
+        (remaining.unwrap_or(usize::MAX), remaining.ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::rng::XorShift;
+
+    use crate::{springs_to_string, unfold, SpringSolver, Springs};
+
+    fn solver(springs: &str, chains: &[u32]) -> SpringSolver {
+        SpringSolver::new(
+            springs
+                .chars()
+                .map(|c| match c {
+                    '#' => Springs::Y,
+                    '.' => Springs::N,
+                    _ => Springs::M,
+                })
+                .collect(),
+            chains.to_vec(),
+        )
+    }
+
+    #[test]
+    fn arrangements() {
+        let mut solver = solver("?###????????", &[3, 2, 1]);
+
+        assert_eq!(solver.solve((0, 0, 0)), Some(10));
+
+        let all = solver
+            .arrangements()
+            .map(|a| springs_to_string(&a))
+            .collect::<Vec<_>>();
+
+        assert_eq!(all.len(), 10);
+        assert_eq!(all[0], ".###.##.#...");
+        assert_eq!(all[9], ".###....##.#");
+        assert!(all.windows(2).all(|w| w[0] != w[1]));
+    }
+
+    #[test]
+    fn nth() {
+        let mut solver = solver("???.###", &[1, 1, 3]);
+
+        assert_eq!(
+            solver.nth(0).map(|a| springs_to_string(&a)),
+            Some("#.#.###".to_string())
+        );
+        assert_eq!(solver.nth(1), None);
+        assert_eq!(solver.sample(&mut XorShift::default()), solver.nth(0));
+    }
+
+    #[test]
+    fn unfolded() {
+        let count = |springs: &str, chains: &[u32], factor| {
+            let s = solver(springs, chains);
+            let (springs, chains) = unfold(&s.springs, &s.chains, factor);
+            SpringSolver::new(springs, chains).solve((0, 0, 0))
+        };
 
-        ans
+        assert_eq!(count("?###????????", &[3, 2, 1], 5), Some(506250));
+        assert_eq!(count(".??..??...?##.", &[1, 1, 3], 5), Some(16384));
+        assert_eq!(count(".??..??...?##.", &[1, 1, 3], 22), Some(1 << 65));
+        assert_eq!(count("????.######..#####.", &[1, 6, 5], 1), Some(4));
+        assert_eq!(count("???", &[1], 150), None);
+        // deeper than the call stack would go
+        assert_eq!(count("#", &[1], 50_000), Some(1));
+        assert_eq!(count("?#?", &[2], 20_000), None);
     }
 }
@@ -0,0 +1,254 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{anyhow, bail, Error, Result};
+
+use crate::{SpringSolver, Springs};
+
+/// a 2d nonogram, where every row and column is a line of springs with its
+/// clue as the chain lengths
+#[derive(Debug)]
+pub struct Nonogram {
+    rows: Vec<Vec<u32>>,
+    columns: Vec<Vec<u32>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Solution {
+    Unique(Picture),
+    /// two of the pictures that fit the clues
+    Multiple(Picture, Picture),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Line {
+    Row(usize),
+    Column(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Picture {
+    cells: Vec<Vec<Springs>>,
+}
+
+impl Nonogram {
+    pub fn solve(&self) -> Result<Solution> {
+        let mut picture = Picture {
+            cells: vec![vec![Springs::M; self.columns.len()]; self.rows.len()],
+        };
+
+        if let Err(line) = self.propagate(&mut picture) {
+            bail!("the clues for {line:?} contradict the rest of the puzzle");
+        }
+
+        let mut solutions = vec![];
+        self.search(picture, &mut solutions);
+
+        match solutions.len() {
+            0 => bail!("no picture fits the clues, every guess leads to a contradiction"),
+            1 => Ok(Solution::Unique(solutions.pop().unwrap())),
+            _ => {
+                let second = solutions.pop().unwrap();
+                Ok(Solution::Multiple(solutions.pop().unwrap(), second))
+            }
+        }
+    }
+
+    /// backtracks on the first unknown cell until two solutions are found
+    fn search(&self, picture: Picture, solutions: &mut Vec<Picture>) {
+        let Some((i, j)) = picture.first_unknown() else {
+            solutions.push(picture);
+            return;
+        };
+
+        for guess in [Springs::Y, Springs::N] {
+            if solutions.len() >= 2 {
+                return;
+            }
+
+            let mut next = picture.clone();
+            next.cells[i][j] = guess;
+
+            if self.propagate(&mut next).is_ok() {
+                self.search(next, solutions);
+            }
+        }
+    }
+
+    /// line solves every row and column until nothing changes, returning the
+    /// line that has no valid arrangement left if there is one
+    fn propagate(&self, picture: &mut Picture) -> Result<(), Line> {
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for (i, clue) in self.rows.iter().enumerate() {
+                let row = picture.cells[i].clone();
+                let solved = solve_line(&row, clue).ok_or(Line::Row(i))?;
+
+                if solved != row {
+                    picture.cells[i] = solved;
+                    changed = true;
+                }
+            }
+
+            for (j, clue) in self.columns.iter().enumerate() {
+                let column = picture.cells.iter().map(|r| r[j]).collect::<Vec<_>>();
+                let solved = solve_line(&column, clue).ok_or(Line::Column(j))?;
+
+                if solved != column {
+                    for (row, s) in picture.cells.iter_mut().zip(solved) {
+                        row[j] = s;
+                    }
+                    changed = true;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// fills in every unknown cell of the line that has the same value in all of
+/// its arrangements, or `None` if there are no arrangements
+fn solve_line(line: &[Springs], chains: &[u32]) -> Option<Vec<Springs>> {
+    if SpringSolver::new(line.to_vec(), chains.to_vec()).solve((0, 0, 0)) == Some(0) {
+        return None;
+    }
+
+    let mut solved = line.to_vec();
+
+    for (i, _) in line.iter().enumerate().filter(|(_, &s)| s == Springs::M) {
+        let count_with = |s| {
+            let mut line = line.to_vec();
+            line[i] = s;
+            SpringSolver::new(line, chains.to_vec()).solve((0, 0, 0))
+        };
+
+        if count_with(Springs::Y) == Some(0) {
+            solved[i] = Springs::N;
+        } else if count_with(Springs::N) == Some(0) {
+            solved[i] = Springs::Y;
+        }
+    }
+
+    Some(solved)
+}
+
+impl Picture {
+    fn first_unknown(&self) -> Option<(usize, usize)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .find_map(|(i, r)| r.iter().position(|&s| s == Springs::M).map(|j| (i, j)))
+    }
+}
+
+impl Display for Picture {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in &self.cells {
+            writeln!(f, "{}", crate::springs_to_string(row))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// parses the `.non` format, where `rows` and `columns` are each followed by
+/// one comma separated clue per line (`0` for an empty line) up to the next
+/// blank line or keyword, and other keywords are ignored
+impl FromStr for Nonogram {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut width = None;
+        let mut height = None;
+        let mut rows = vec![];
+        let mut columns = vec![];
+        let mut section = None;
+
+        for line in s.lines().map(str::trim) {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+
+            match key {
+                "width" => width = Some(value.trim().parse::<usize>()?),
+                "height" => height = Some(value.trim().parse::<usize>()?),
+                "rows" => section = Some(&mut rows),
+                "columns" => section = Some(&mut columns),
+                _ => match section.as_mut() {
+                    Some(clues) if line.starts_with(|c: char| c.is_ascii_digit()) => {
+                        clues.push(
+                            line.split(',')
+                                .map(str::trim)
+                                .filter(|c| !c.is_empty())
+                                .map(u32::from_str)
+                                .filter(|c| c != &Ok(0))
+                                .collect::<Result<Vec<_>, _>>()?,
+                        );
+                    }
+                    _ => section = None,
+                },
+            }
+        }
+
+        let width = width.unwrap_or(columns.len());
+        let height = height.unwrap_or(rows.len());
+
+        if rows.len() != height || columns.len() != width {
+            return Err(anyhow!(
+                "expected {height} rows and {width} columns but found {} and {}",
+                rows.len(),
+                columns.len()
+            ));
+        }
+
+        Ok(Self { rows, columns })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Nonogram, Solution};
+
+    #[test]
+    fn unique() {
+        let nonogram = "width 5
+height 5
+
+rows
+1
+3
+5
+1,1
+1,1
+
+columns
+1
+4
+3
+4
+1"
+        .parse::<Nonogram>()
+        .unwrap();
+
+        let Solution::Unique(picture) = nonogram.solve().unwrap() else {
+            panic!("expected a unique solution");
+        };
+
+        assert_eq!(picture.to_string(), "..#..\n.###.\n#####\n.#.#.\n.#.#.\n");
+    }
+
+    #[test]
+    fn multiple() {
+        let nonogram = "rows\n1\n1\ncolumns\n1\n1".parse::<Nonogram>().unwrap();
+
+        assert!(matches!(nonogram.solve().unwrap(), Solution::Multiple(..)));
+    }
+
+    #[test]
+    fn contradiction() {
+        let nonogram = "rows\n2\n0\ncolumns\n1\n0".parse::<Nonogram>().unwrap();
+
+        assert!(nonogram.solve().is_err());
+    }
+}
@@ -1,28 +1,33 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
+use anyhow::{bail, Error, Result};
 use shared::dprintln;
 
 const INUPUT: &str = include_str!("../input");
 
-fn main() {
+fn main() -> Result<()> {
     let patterns = INUPUT
         .split("\n\n")
-        .map(|p| {
-            let vcount = p.lines().next().unwrap().chars().count();
+        .map(ReflectionPattern::from_str)
+        .collect::<Result<Vec<_>>>()?;
 
-            let (pattern, hcount) = p.lines().map(|l| l.chars().map(|c| c == '#')).fold(
-                (vec![], 0),
-                |(mut total, mut count), next| {
-                    next.for_each(|v| total.push(v));
-                    count += 1;
+    // `day13 symmetries [k]` lists every way each pattern maps onto itself
+    // with at most k smudges instead of solving
+    let mut args = std::env::args().skip(1);
 
-                    (total, count)
-                },
-            );
+    if args.next().as_deref() == Some("symmetries") {
+        let k = args.next().map(|k| k.parse()).transpose()?.unwrap_or(0);
 
-            ReflectionPattern::new(pattern, hcount, vcount)
-        })
-        .collect::<Vec<_>>();
+        for (i, pattern) in patterns.iter().enumerate() {
+            println!("{i}:");
+
+            for (symmetry, smudges) in (0..=k).flat_map(|k| pattern.symmetries(k)) {
+                println!("    {symmetry:?} with smudges {smudges:?}");
+            }
+        }
+
+        return Ok(());
+    }
 
     let mut columns_left_1 = 0;
     let mut rows_above_1 = 0;
@@ -30,142 +35,357 @@ fn main() {
     let mut columns_left_2 = 0;
     let mut rows_above_2 = 0;
 
-    'patterns: for (i, mut pattern) in patterns.into_iter().enumerate() {
-        let line = pattern.find_line(None).unwrap();
+    for (i, pattern) in patterns.iter().enumerate() {
+        let Some(clean) = pattern.reflections(0).into_iter().next() else {
+            bail!("pattern {i} has no reflection line");
+        };
 
-        match line {
+        match clean.line {
             Line::Vertical(c) => columns_left_1 += c,
             Line::Horizontal(r) => rows_above_1 += r,
         }
 
-        for smudge in 0..pattern.pattern.len() {
-            pattern.flip(smudge);
-
-            // dprintln!("{pattern}");
+        let Some(smudged) = pattern.reflections(1).into_iter().next() else {
+            bail!("pattern {i} has no reflection line with a single smudge");
+        };
 
-            let line = pattern.find_line(Some(line));
-
-            if let Some(v) = line {
-                match v {
-                    Line::Vertical(c) => columns_left_2 += c,
-                    Line::Horizontal(r) => rows_above_2 += r,
-                }
-
-                dprintln!("sm: {smudge}");
-                continue 'patterns;
-            };
-
-            pattern.flip(smudge);
+        match smudged.line {
+            Line::Vertical(c) => columns_left_2 += c,
+            Line::Horizontal(r) => rows_above_2 += r,
         }
 
-        println!("{i}");
-
-        unreachable!();
+        dprintln!("{i}: {:?} sm: {:?}", smudged.line, smudged.smudges);
     }
 
     println!("part1: {}", rows_above_1 * 100 + columns_left_1);
     println!("part2: {}", rows_above_2 * 100 + columns_left_2);
+
+    Ok(())
 }
 
+/// a pattern stored both as row bitmasks (bit `j` of `rows[i]` is the cell at
+/// `(i, j)`) and column bitmasks (bit `i` of `columns[j]`), so comparing two
+/// rows or two columns is a xor and a popcount
 #[derive(Debug)]
 struct ReflectionPattern {
-    pattern: Vec<bool>,
-    hcount: usize,
-    vcount: usize,
+    rows: Vec<u64>,
+    columns: Vec<u64>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Line {
     Vertical(usize),
     Horizontal(usize),
 }
 
+/// a pair of cells that should be equal under a symmetry but aren't
+type Smudge = [(usize, usize); 2];
+
+/// any way a pattern can map onto itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Symmetry {
+    Mirror(Line),
+    /// a half turn about the center
+    Rotation,
+    /// a reflection across the top left to bottom right diagonal
+    Diagonal,
+    /// a reflection across the top right to bottom left diagonal
+    AntiDiagonal,
+}
+
+/// a reflection line along with the mirrored pairs of cells that don't match
+#[derive(Debug, PartialEq, Eq)]
+struct Reflection {
+    line: Line,
+    smudges: Vec<Smudge>,
+}
+
 impl ReflectionPattern {
-    fn new(pattern: Vec<bool>, hcount: usize, vcount: usize) -> Self {
-        Self {
-            pattern,
-            hcount,
-            vcount,
-        }
+    fn vcount(&self) -> usize {
+        self.columns.len()
     }
 
-    fn flip(&mut self, smudge: usize) {
-        self.pattern[smudge] = !self.pattern[smudge];
+    /// every reflection line with exactly `k` mismatched pairs of cells,
+    /// vertical lines first
+    fn reflections(&self, k: u32) -> Vec<Reflection> {
+        let vertical = mirror_lines(&self.columns, k).map(|(c, smudges)| Reflection {
+            line: Line::Vertical(c),
+            smudges: smudges
+                .into_iter()
+                .map(|(i, j1, j2)| [(i, j1), (i, j2)])
+                .collect(),
+        });
+
+        let horizontal = mirror_lines(&self.rows, k).map(|(r, smudges)| Reflection {
+            line: Line::Horizontal(r),
+            smudges: smudges
+                .into_iter()
+                .map(|(j, i1, i2)| [(i1, j), (i2, j)])
+                .collect(),
+        });
+
+        vertical.chain(horizontal).collect()
     }
 
-    fn match_vertical(&self, j1: usize, j2: usize) -> bool {
-        debug_assert!(j1 < j2);
+    /// every symmetry with exactly `k` mismatched pairs of cells, along with
+    /// those pairs, with the diagonals only checked on square patterns
+    fn symmetries(&self, k: u32) -> Vec<(Symmetry, Vec<Smudge>)> {
+        let (h, w) = (self.rows.len(), self.columns.len());
+
+        let mut symmetries = self
+            .reflections(k)
+            .into_iter()
+            .map(|r| (Symmetry::Mirror(r.line), r.smudges))
+            .collect::<Vec<_>>();
+
+        symmetries.push((
+            Symmetry::Rotation,
+            self.mismatches(
+                |i| reverse(self.rows[h - 1 - i], w),
+                |i, j| (h - 1 - i, w - 1 - j),
+            ),
+        ));
+
+        if h == w {
+            symmetries.push((
+                Symmetry::Diagonal,
+                self.mismatches(|i| self.columns[i], |i, j| (j, i)),
+            ));
+
+            symmetries.push((
+                Symmetry::AntiDiagonal,
+                self.mismatches(
+                    |i| reverse(self.columns[w - 1 - i], h),
+                    |i, j| (w - 1 - j, h - 1 - i),
+                ),
+            ));
+        }
+
+        symmetries
+            .retain(|(s, smudges)| matches!(s, Symmetry::Mirror(_)) || smudges.len() == k as usize);
 
-        j2 >= self.vcount
-            || (0..self.hcount)
-                .map(|i| i * self.vcount)
-                .all(|i| self.pattern[i + j1] == self.pattern[i + j2])
+        symmetries
     }
 
-    fn match_horizontal(&self, i1: usize, i2: usize) -> bool {
-        debug_assert!(i1 < i2);
-
-        i2 >= self.hcount
-            || (0..self.vcount)
-                .all(|j| self.pattern[i1 * self.vcount + j] == self.pattern[i2 * self.vcount + j])
+    /// the pairs of cells that differ when each row `i` is compared bitwise to
+    /// `image(i)`, where `partner` is the cell that `(i, j)` is compared with
+    fn mismatches(
+        &self,
+        image: impl Fn(usize) -> u64,
+        partner: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Vec<Smudge> {
+        self.rows
+            .iter()
+            .enumerate()
+            .flat_map(|(i, row)| bits(row ^ image(i)).map(move |j| (i, j)))
+            .filter_map(|cell| {
+                let other = partner(cell.0, cell.1);
+                (cell < other).then_some([cell, other])
+            })
+            .collect()
     }
+}
 
-    fn find_line(&self, not: Option<Line>) -> Option<Line> {
-        self.find_vertical_line(not)
-            .or_else(|| self.find_horizontal_line(not))
+/// the lowest `width` bits of `mask` in reverse order
+fn reverse(mask: u64, width: usize) -> u64 {
+    if width == 0 {
+        0
+    } else {
+        mask.reverse_bits() >> (64 - width)
     }
+}
 
-    fn find_vertical_line(&self, not: Option<Line>) -> Option<Line> {
-        'outer: for i in 0..self.vcount - 1 {
-            for curr in 0..=i {
-                if !self.match_vertical(i - curr, i + curr + 1) {
-                    continue 'outer;
-                }
+/// every line between two of the `lines` with exactly `k` mismatches, as the
+/// number of lines before it and the mismatches as `(bit, line, mirrored line)`
+fn mirror_lines(
+    lines: &[u64],
+    k: u32,
+) -> impl Iterator<Item = (usize, Vec<(usize, usize, usize)>)> + '_ {
+    (1..lines.len()).filter_map(move |axis| {
+        let pairs = (0..axis.min(lines.len() - axis)).map(|d| (axis - 1 - d, axis + d));
+
+        let mut mismatches = 0;
+        for (a, b) in pairs.clone() {
+            mismatches += (lines[a] ^ lines[b]).count_ones();
+
+            if mismatches > k {
+                return None;
             }
+        }
 
-            let line = i + 1;
+        if mismatches != k {
+            return None;
+        }
 
-            if matches!(not, Some(Line::Vertical(l)) if l == line) {
-                continue;
-            }
+        let smudges = pairs
+            .flat_map(|(a, b)| bits(lines[a] ^ lines[b]).map(move |bit| (bit, a, b)))
+            .collect();
+
+        Some((axis, smudges))
+    })
+}
 
-            return Some(Line::Vertical(line));
+/// the indices of the set bits in `mask`
+fn bits(mut mask: u64) -> impl Iterator<Item = usize> {
+    std::iter::from_fn(move || {
+        if mask == 0 {
+            return None;
         }
 
-        None
-    }
+        let bit = mask.trailing_zeros() as usize;
+        mask &= mask - 1;
 
-    fn find_horizontal_line(&self, not: Option<Line>) -> Option<Line> {
-        'outer: for j in 0..self.hcount - 1 {
-            for curr in 0..=j {
-                if !self.match_horizontal(j - curr, j + curr + 1) {
-                    continue 'outer;
-                }
-            }
+        Some(bit)
+    })
+}
+
+impl FromStr for ReflectionPattern {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let vcount = s.lines().next().map_or(0, |l| l.chars().count());
+        let hcount = s.lines().count();
+
+        if vcount > 64 || hcount > 64 {
+            bail!("patterns can be at most 64 by 64, found {hcount} by {vcount}");
+        }
 
-            let line = j + 1;
+        let mut rows = vec![0; hcount];
+        let mut columns = vec![0; vcount];
 
-            if matches!(not, Some(Line::Horizontal(l)) if l == line) {
-                continue;
+        for (i, line) in s.lines().enumerate() {
+            if line.chars().count() != vcount {
+                bail!("line {i} of the pattern isn't {vcount} wide");
             }
 
-            return Some(Line::Horizontal(line));
+            for (j, c) in line.chars().enumerate() {
+                match c {
+                    '#' => {
+                        rows[i] |= 1 << j;
+                        columns[j] |= 1 << i;
+                    }
+                    '.' => (),
+                    _ => bail!("the character {c} is not valid in a pattern"),
+                }
+            }
         }
 
-        None
+        Ok(Self { rows, columns })
     }
 }
 
 impl Display for ReflectionPattern {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (i, &c) in self.pattern.iter().enumerate() {
-            if i % self.vcount == self.vcount - 1 {
-                writeln!(f, "{}", if c { '#' } else { '.' })?
-            } else {
-                write!(f, "{}", if c { '#' } else { '.' })?
+        for row in &self.rows {
+            for j in 0..self.vcount() {
+                write!(f, "{}", if row >> j & 1 == 1 { '#' } else { '.' })?
             }
+
+            writeln!(f)?
         }
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Line, Reflection, ReflectionPattern, Symmetry};
+
+    const FIRST: &str = "#.##..##.
+..#.##.#.
+##......#
+##......#
+..#.##.#.
+..##..##.
+#.#.##.#.";
+
+    const SECOND: &str = "#...##..#
+#....#..#
+..##..###
+#####.##.
+#####.##.
+..##..###
+#....#..#";
+
+    #[test]
+    fn reflections() {
+        let first = FIRST.parse::<ReflectionPattern>().unwrap();
+        let second = SECOND.parse::<ReflectionPattern>().unwrap();
+
+        assert_eq!(
+            first.reflections(0),
+            vec![Reflection {
+                line: Line::Vertical(5),
+                smudges: vec![]
+            }]
+        );
+        assert_eq!(
+            second.reflections(0),
+            vec![Reflection {
+                line: Line::Horizontal(4),
+                smudges: vec![]
+            }]
+        );
+
+        assert_eq!(
+            first.reflections(1),
+            vec![Reflection {
+                line: Line::Horizontal(3),
+                smudges: vec![[(0, 0), (5, 0)]]
+            }]
+        );
+        assert_eq!(
+            second.reflections(1),
+            vec![Reflection {
+                line: Line::Horizontal(1),
+                smudges: vec![[(0, 4), (1, 4)]]
+            }]
+        );
+    }
+
+    #[test]
+    fn symmetries() {
+        let symmetric = "#..\n.#.\n..#".parse::<ReflectionPattern>().unwrap();
+
+        assert_eq!(
+            symmetric
+                .symmetries(0)
+                .into_iter()
+                .map(|s| s.0)
+                .collect::<Vec<_>>(),
+            vec![
+                Symmetry::Rotation,
+                Symmetry::Diagonal,
+                Symmetry::AntiDiagonal
+            ]
+        );
+
+        let smudged = "##.\n.#.\n..#".parse::<ReflectionPattern>().unwrap();
+
+        assert_eq!(
+            smudged.symmetries(1),
+            vec![
+                (Symmetry::Mirror(Line::Vertical(1)), vec![[(1, 0), (1, 1)]]),
+                (
+                    Symmetry::Mirror(Line::Horizontal(1)),
+                    vec![[(0, 0), (1, 0)]]
+                ),
+                (Symmetry::Rotation, vec![[(0, 1), (2, 1)]]),
+                (Symmetry::Diagonal, vec![[(0, 1), (1, 0)]]),
+                (Symmetry::AntiDiagonal, vec![[(0, 1), (1, 2)]]),
+            ]
+        );
+
+        let wide = "#..\n..#".parse::<ReflectionPattern>().unwrap();
+
+        assert_eq!(wide.symmetries(0), vec![(Symmetry::Rotation, vec![])]);
+    }
+
+    #[test]
+    fn display() {
+        let first = FIRST.parse::<ReflectionPattern>().unwrap();
+
+        assert_eq!(first.to_string(), format!("{FIRST}\n"));
+    }
+}
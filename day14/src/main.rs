@@ -1,8 +1,14 @@
-use std::{collections::HashMap, fmt::Display, str::FromStr};
+use std::{fmt::Display, str::FromStr};
 
 use anyhow::{anyhow, bail, Error, Result};
 use shared::dprintln;
 
+use packed::{PackedGrid, Tilt};
+use program::Program;
+
+mod packed;
+mod program;
+
 const INPUT: &str = include_str!("../input");
 
 fn main() -> Result<()> {
@@ -18,50 +24,29 @@ fn main() -> Result<()> {
 
     println!("part1: {load}");
 
-    let mut prev_occurences = HashMap::<_, Vec<_>>::new();
-
-    let mut loads = vec![];
-
-    for i in 0usize..200 {
-        inp.cycle();
-
-        let load = inp.north_load();
-
-        loads.push(load);
-
-        let prev_times = prev_occurences
-            .entry(load)
-            .and_modify(|prev_times| prev_times.push(i))
-            .or_insert(vec![i]);
-
-        println!("{i: >3}: {} {:?}", load, prev_times)
-    }
-
-    let mut stdio = std::io::stdin()
-        .lines()
-        .map(|l| l?.parse::<usize>().map_err(|e| anyhow!(e)));
-
-    println!("enter the first line it cycled at > ");
-
-    let first = stdio.next().unwrap()?;
+    // part 2 runs on the packed grid, checked against a cycle of the
+    // reference grid in debug builds. tilting north first doesn't change the
+    // result of a cycle, so both can start from the part 1 grid
+    let mut packed = PackedGrid::from(&inp);
 
-    println!("enter the next line it cycled at > ");
-    let next = stdio.next().unwrap()?;
+    inp.cycle();
+    packed.cycle();
+    debug_assert_eq!(inp.to_string(), packed.to_string());
 
-    let cycle_length = next - first;
+    "N W S E x999999999".parse::<Program>()?.run(&mut packed);
 
-    for (i, load) in (first..).zip(loads[first..].iter()) {
-        let index = ((i - first) % cycle_length) + first;
+    println!("part2: {}", packed.load(Tilt::North));
 
-        assert_eq!(loads[index], *load, "you picked the numbers wrong");
+    // `day14 loads` also prints the load on every edge after the spin cycles
+    if std::env::args().nth(1).as_deref() == Some("loads") {
+        for edge in [Tilt::North, Tilt::West, Tilt::South, Tilt::East] {
+            println!("{edge:?} load: {}", packed.load(edge));
+        }
     }
 
-    let index = ((1000000000 - 1 - first) % cycle_length) + first;
-
-    println!("part2: {}", loads[index]);
-
     Ok(())
 }
+
 #[derive(Debug, Clone, Copy)]
 enum Rock {
     Cube,
@@ -0,0 +1,399 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{Grid, Rock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tilt {
+    North,
+    West,
+    South,
+    East,
+}
+
+pub const CYCLE: [Tilt; 4] = [Tilt::North, Tilt::West, Tilt::South, Tilt::East];
+
+/// a grid with the rounded and cube rocks packed into one bitset per row, bit
+/// `j % 64` of word `j / 64` being column `j`
+///
+/// tilting north or south moves whole words of rocks a row at a time, and
+/// tilting east or west counts the rocks in each run between cube rocks and
+/// refills the run from one end
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackedGrid {
+    round: Vec<u64>,
+    cube: Vec<u64>,
+    /// the `start..end` column ranges without cube rocks in each row
+    runs: Vec<Vec<(usize, usize)>>,
+    words: usize,
+    row_len: usize,
+    col_height: usize,
+}
+
+impl PackedGrid {
+    pub fn cycle(&mut self) {
+        self.run(&CYCLE);
+    }
+
+    pub fn tilt(&mut self, tilt: Tilt) {
+        match tilt {
+            Tilt::North => self.move_north(),
+            Tilt::West => self.move_west(),
+            Tilt::South => self.move_south(),
+            Tilt::East => self.move_east(),
+        }
+    }
+
+    pub fn run(&mut self, tilts: &[Tilt]) {
+        for &tilt in tilts {
+            self.tilt(tilt);
+        }
+    }
+
+    /// runs the tilts `n` times, skipping ahead once the rocks return to an
+    /// earlier position
+    pub fn repeat(&mut self, tilts: &[Tilt], n: usize) {
+        let mut seen = HashMap::new();
+        let mut states = vec![];
+
+        for i in 0..n {
+            if let Some(&first) = seen.get(&self.round) {
+                let cycle_length = i - first;
+                self.round = states.swap_remove(first + (n - first) % cycle_length);
+                return;
+            }
+
+            seen.insert(self.round.clone(), i);
+            states.push(self.round.clone());
+
+            self.run(tilts);
+        }
+    }
+
+    pub fn move_north(&mut self) {
+        for i in 1..self.col_height {
+            for k in 0..self.words {
+                let mut moving = self.round[i * self.words + k];
+                self.round[i * self.words + k] = 0;
+
+                let mut r = i;
+                while r > 0 && moving != 0 {
+                    let above = (r - 1) * self.words + k;
+                    let up = moving & !(self.round[above] | self.cube[above]);
+
+                    self.round[r * self.words + k] |= moving & !up;
+                    moving = up;
+                    r -= 1;
+                }
+
+                self.round[r * self.words + k] |= moving;
+            }
+        }
+    }
+
+    pub fn move_south(&mut self) {
+        for i in (0..self.col_height.saturating_sub(1)).rev() {
+            for k in 0..self.words {
+                let mut moving = self.round[i * self.words + k];
+                self.round[i * self.words + k] = 0;
+
+                let mut r = i;
+                while r + 1 < self.col_height && moving != 0 {
+                    let below = (r + 1) * self.words + k;
+                    let down = moving & !(self.round[below] | self.cube[below]);
+
+                    self.round[r * self.words + k] |= moving & !down;
+                    moving = down;
+                    r += 1;
+                }
+
+                self.round[r * self.words + k] |= moving;
+            }
+        }
+    }
+
+    pub fn move_west(&mut self) {
+        for i in 0..self.col_height {
+            for r in 0..self.runs[i].len() {
+                let (start, end) = self.runs[i][r];
+                let count = self.count_range(i, start, end);
+
+                self.set_range(i, start, end, false);
+                self.set_range(i, start, start + count, true);
+            }
+        }
+    }
+
+    pub fn move_east(&mut self) {
+        for i in 0..self.col_height {
+            for r in 0..self.runs[i].len() {
+                let (start, end) = self.runs[i][r];
+                let count = self.count_range(i, start, end);
+
+                self.set_range(i, start, end, false);
+                self.set_range(i, end - count, end, true);
+            }
+        }
+    }
+
+    pub fn north_load(&self) -> usize {
+        self.round
+            .chunks(self.words)
+            .enumerate()
+            .map(|(i, row)| {
+                row.iter().map(|w| w.count_ones() as usize).sum::<usize>() * (self.col_height - i)
+            })
+            .sum()
+    }
+
+    /// the load on the `edge` side, where each rounded rock counts for its
+    /// distance from the opposite edge
+    pub fn load(&self, edge: Tilt) -> usize {
+        match edge {
+            Tilt::North => self.north_load(),
+            Tilt::South => self
+                .round
+                .chunks(self.words)
+                .enumerate()
+                .map(|(i, row)| {
+                    row.iter().map(|w| w.count_ones() as usize).sum::<usize>() * (i + 1)
+                })
+                .sum(),
+            Tilt::West | Tilt::East => self
+                .round
+                .chunks(self.words)
+                .flat_map(|row| {
+                    row.iter().enumerate().flat_map(|(k, &word)| {
+                        (0..64)
+                            .filter(move |b| word >> b & 1 == 1)
+                            .map(move |b| k * 64 + b)
+                    })
+                })
+                .map(|j| {
+                    if edge == Tilt::West {
+                        self.row_len - j
+                    } else {
+                        j + 1
+                    }
+                })
+                .sum(),
+        }
+    }
+
+    /// the masks of the words that cover the columns `start..end`
+    fn range_masks(&self, start: usize, end: usize) -> impl Iterator<Item = (usize, u64)> {
+        (start / 64..end.div_ceil(64)).map(move |k| {
+            let lo = start.max(k * 64) - k * 64;
+            let hi = end.min(k * 64 + 64) - k * 64;
+
+            let mask = if hi - lo == 64 {
+                u64::MAX
+            } else {
+                ((1 << (hi - lo)) - 1) << lo
+            };
+
+            (k, mask)
+        })
+    }
+
+    fn count_range(&self, i: usize, start: usize, end: usize) -> usize {
+        self.range_masks(start, end)
+            .map(|(k, mask)| (self.round[i * self.words + k] & mask).count_ones() as usize)
+            .sum()
+    }
+
+    fn set_range(&mut self, i: usize, start: usize, end: usize, value: bool) {
+        if start == end {
+            return;
+        }
+
+        for (k, mask) in self.range_masks(start, end).collect::<Vec<_>>() {
+            if value {
+                self.round[i * self.words + k] |= mask;
+            } else {
+                self.round[i * self.words + k] &= !mask;
+            }
+        }
+    }
+
+    fn get(&self, i: usize, j: usize) -> Rock {
+        let bit = 1 << (j % 64);
+        let word = i * self.words + j / 64;
+
+        if self.cube[word] & bit != 0 {
+            Rock::Cube
+        } else if self.round[word] & bit != 0 {
+            Rock::Sphere
+        } else {
+            Rock::None
+        }
+    }
+}
+
+impl From<&Grid> for PackedGrid {
+    fn from(grid: &Grid) -> Self {
+        let words = grid.row_len.div_ceil(64);
+
+        let mut round = vec![0; words * grid.col_height];
+        let mut cube = vec![0; words * grid.col_height];
+        let mut runs = vec![vec![]; grid.col_height];
+
+        for (i, row_runs) in runs.iter_mut().enumerate() {
+            let mut start = 0;
+
+            for (j, rock) in grid.get_row(i) {
+                let word = i * words + j / 64;
+
+                match rock {
+                    Rock::Cube => {
+                        cube[word] |= 1 << (j % 64);
+
+                        if start < j {
+                            row_runs.push((start, j));
+                        }
+                        start = j + 1;
+                    }
+                    Rock::Sphere => round[word] |= 1 << (j % 64),
+                    Rock::None => (),
+                }
+            }
+
+            if start < grid.row_len {
+                row_runs.push((start, grid.row_len));
+            }
+        }
+
+        Self {
+            round,
+            cube,
+            runs,
+            words,
+            row_len: grid.row_len,
+            col_height: grid.col_height,
+        }
+    }
+}
+
+impl Display for PackedGrid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for i in 0..self.col_height {
+            for j in 0..self.row_len {
+                write!(f, "{} ", self.get(i, j))?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::rng::XorShift;
+
+    use super::{PackedGrid, Tilt, CYCLE};
+    use crate::Grid;
+
+    const SAMPLE: &str = "O....#....
+O.OO#....#
+.....##...
+OO.#O....O
+.O.....O#.
+O.#..O.#.#
+..O..#O..O
+.......O..
+#....###..
+#OO..#....";
+
+    /// from a seeded generator so the grids are reproducible
+    fn random_grids(count: usize) -> impl Iterator<Item = String> {
+        let mut rng = XorShift::default();
+        let mut next = move || rng.next_u64();
+
+        (0..count).map(move |_| {
+            let height = 1 + (next() % 20) as usize;
+            let width = 1 + (next() % 150) as usize;
+
+            (0..height)
+                .map(|_| {
+                    (0..width)
+                        .map(|_| match next() % 5 {
+                            0 => '#',
+                            1 | 2 => 'O',
+                            _ => '.',
+                        })
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+    }
+
+    #[test]
+    fn sample() {
+        let grid = SAMPLE.parse::<Grid>().unwrap();
+        let mut packed = PackedGrid::from(&grid);
+
+        packed.move_north();
+        assert_eq!(packed.north_load(), 136);
+
+        let mut packed = PackedGrid::from(&grid);
+        packed.repeat(&CYCLE, 1_000_000_000);
+        assert_eq!(packed.north_load(), 64);
+    }
+
+    #[test]
+    fn loads() {
+        let packed = PackedGrid::from(&"O.#\n..O".parse::<Grid>().unwrap());
+
+        assert_eq!(packed.load(Tilt::North), 3);
+        assert_eq!(packed.load(Tilt::South), 3);
+        assert_eq!(packed.load(Tilt::West), 4);
+        assert_eq!(packed.load(Tilt::East), 4);
+    }
+
+    #[test]
+    fn matches_grid() {
+        for input in random_grids(200) {
+            let mut grid = input.parse::<Grid>().unwrap();
+            let mut packed = PackedGrid::from(&grid);
+
+            assert_eq!(grid.to_string(), packed.to_string());
+
+            for _ in 0..3 {
+                grid.move_north();
+                packed.move_north();
+                assert_eq!(grid.to_string(), packed.to_string(), "north of\n{input}");
+
+                grid.move_west();
+                packed.move_west();
+                assert_eq!(grid.to_string(), packed.to_string(), "west of\n{input}");
+
+                grid.move_south();
+                packed.move_south();
+                assert_eq!(grid.to_string(), packed.to_string(), "south of\n{input}");
+
+                grid.move_east();
+                packed.move_east();
+                assert_eq!(grid.to_string(), packed.to_string(), "east of\n{input}");
+
+                assert_eq!(grid.north_load(), packed.north_load());
+            }
+        }
+    }
+
+    #[test]
+    fn spin_matches_grid() {
+        for input in random_grids(10) {
+            let mut grid = input.parse::<Grid>().unwrap();
+            let mut packed = PackedGrid::from(&grid);
+
+            packed.repeat(&CYCLE, 300);
+            for _ in 0..300 {
+                grid.cycle();
+            }
+
+            assert_eq!(grid.to_string(), packed.to_string());
+        }
+    }
+}
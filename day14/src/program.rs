@@ -0,0 +1,124 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Error, Result};
+
+use crate::packed::{PackedGrid, Tilt};
+
+/// a list of tilts to run, like `N W S E x1000000000`
+///
+/// directions are collected until an `x<count>`, which repeats everything
+/// since the previous count, so `N x1 W S x3 E` tilts north once, then west
+/// and south three times, then east once
+#[derive(Debug, PartialEq, Eq)]
+pub struct Program {
+    blocks: Vec<(Vec<Tilt>, usize)>,
+}
+
+impl Program {
+    /// runs the program, skipping ahead through each repeated block once the
+    /// rocks start cycling
+    pub fn run(&self, grid: &mut PackedGrid) {
+        for (tilts, count) in &self.blocks {
+            grid.repeat(tilts, *count);
+        }
+    }
+}
+
+impl FromStr for Program {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut blocks = vec![];
+        let mut tilts = vec![];
+
+        for token in s.split_whitespace() {
+            match token {
+                "N" => tilts.push(Tilt::North),
+                "W" => tilts.push(Tilt::West),
+                "S" => tilts.push(Tilt::South),
+                "E" => tilts.push(Tilt::East),
+                _ => {
+                    let count = token
+                        .strip_prefix('x')
+                        .ok_or(anyhow!("{token} is not a direction or a repeat count"))?
+                        .parse()?;
+
+                    if tilts.is_empty() {
+                        bail!("the repeat count {token} has nothing to repeat");
+                    }
+
+                    blocks.push((std::mem::take(&mut tilts), count));
+                }
+            }
+        }
+
+        if !tilts.is_empty() {
+            blocks.push((tilts, 1));
+        }
+
+        Ok(Self { blocks })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Program;
+    use crate::{
+        packed::{PackedGrid, Tilt},
+        Grid,
+    };
+
+    const SAMPLE: &str = "O....#....
+O.OO#....#
+.....##...
+OO.#O....O
+.O.....O#.
+O.#..O.#.#
+..O..#O..O
+.......O..
+#....###..
+#OO..#....";
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "N x1 W S x3 E".parse::<Program>().unwrap(),
+            Program {
+                blocks: vec![
+                    (vec![Tilt::North], 1),
+                    (vec![Tilt::West, Tilt::South], 3),
+                    (vec![Tilt::East], 1)
+                ]
+            }
+        );
+
+        assert!("x3".parse::<Program>().is_err());
+        assert!("N Q".parse::<Program>().is_err());
+        assert!("N xx".parse::<Program>().is_err());
+    }
+
+    #[test]
+    fn run() {
+        let grid = SAMPLE.parse::<Grid>().unwrap();
+
+        let mut packed = PackedGrid::from(&grid);
+        "N W S E x1000000000"
+            .parse::<Program>()
+            .unwrap()
+            .run(&mut packed);
+        assert_eq!(packed.load(Tilt::North), 64);
+
+        let mut other_order = PackedGrid::from(&grid);
+        let mut by_hand = PackedGrid::from(&grid);
+        "S E x999 N x2"
+            .parse::<Program>()
+            .unwrap()
+            .run(&mut other_order);
+        for _ in 0..999 {
+            by_hand.run(&[Tilt::South, Tilt::East]);
+        }
+        by_hand.run(&[Tilt::North]);
+
+        assert_eq!(other_order, by_hand);
+    }
+}
@@ -0,0 +1,321 @@
+use std::{borrow::Borrow, marker::PhantomData};
+
+use crate::ReindeerHash;
+
+/// the HASHMAP from the puzzle: a fixed number of boxes, each keeping its
+/// lenses in the order they were inserted
+///
+/// keys are hashed as `H`, so a `LensMap<String, V, str>` hashes its keys
+/// like string slices, and a different hash function is a key type with its
+/// own [`ReindeerHash`]
+#[derive(Debug, Clone)]
+pub struct LensMap<K, V, H: ?Sized = K> {
+    boxes: Vec<Vec<(K, V)>>,
+    len: usize,
+    hash: PhantomData<fn(&H) -> usize>,
+}
+
+impl<K: Borrow<H> + Eq, V, H: ReindeerHash + ?Sized> LensMap<K, V, H> {
+    /// the puzzle's 256 boxes
+    pub fn new() -> Self {
+        Self::with_buckets(256)
+    }
+
+    /// a map with `buckets` boxes, where a key goes in box
+    /// `key.hash() % buckets`
+    pub fn with_buckets(buckets: usize) -> Self {
+        assert!(buckets > 0, "a LensMap needs at least one box");
+
+        Self {
+            boxes: (0..buckets).map(|_| vec![]).collect(),
+            len: 0,
+            hash: PhantomData,
+        }
+    }
+
+    /// the box a key goes in, where like with a `HashMap` a key borrowed as
+    /// `Q` has to hash the same as the key itself
+    pub fn box_index<Q: ReindeerHash + ?Sized>(&self, key: &Q) -> usize
+    where
+        K: Borrow<Q>,
+    {
+        key.hash() % self.boxes.len()
+    }
+
+    fn position<Q: ReindeerHash + Eq + ?Sized>(&self, key: &Q) -> (usize, Option<usize>)
+    where
+        K: Borrow<Q>,
+    {
+        let b = self.box_index(key);
+
+        (
+            b,
+            self.boxes[b]
+                .iter()
+                .position(|(k, _)| Borrow::<Q>::borrow(k) == key),
+        )
+    }
+
+    /// like [`LensMap::position`], with the key hashed as `H`
+    fn key_position(&self, key: &K) -> (usize, Option<usize>) {
+        let b = self.box_index::<H>(key.borrow());
+
+        (b, self.boxes[b].iter().position(|(k, _)| k == key))
+    }
+
+    /// replaces the value of a lens already in its box without moving it, or
+    /// adds it to the back of the box
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.key_position(&key) {
+            (b, Some(p)) => Some(std::mem::replace(&mut self.boxes[b][p].1, value)),
+            (b, None) => {
+                self.boxes[b].push((key, value));
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    /// takes a lens out of its box, moving the lenses behind it forward
+    pub fn remove<Q: ReindeerHash + Eq + ?Sized>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+    {
+        let (b, p) = self.position(key);
+
+        let (_, value) = self.boxes[b].remove(p?);
+        self.len -= 1;
+
+        Some(value)
+    }
+
+    pub fn get<Q: ReindeerHash + Eq + ?Sized>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+    {
+        let (b, p) = self.position(key);
+
+        Some(&self.boxes[b][p?].1)
+    }
+
+    pub fn get_mut<Q: ReindeerHash + Eq + ?Sized>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+    {
+        let (b, p) = self.position(key);
+
+        Some(&mut self.boxes[b][p?].1)
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        let (b, p) = self.key_position(&key);
+
+        match p {
+            Some(p) => Entry::Occupied(&mut self.boxes[b][p].1),
+            None => Entry::Vacant(VacantEntry {
+                key,
+                lenses: &mut self.boxes[b],
+                len: &mut self.len,
+            }),
+        }
+    }
+}
+
+impl<K: Borrow<H> + Eq, V, H: ReindeerHash + ?Sized> Default for LensMap<K, V, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V, H: ?Sized> LensMap<K, V, H> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// the lenses in box order, and in insertion order within each box
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.boxes.iter().flatten().map(|(k, v)| (k, v))
+    }
+
+    pub fn boxes(&self) -> &[Vec<(K, V)>] {
+        &self.boxes
+    }
+}
+
+impl<K, H: ?Sized> LensMap<K, usize, H> {
+    /// the sum of one plus the box number, times the slot number, times the
+    /// focal length of every lens
+    pub fn focusing_power(&self) -> usize {
+        self.boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, b)| {
+                b.iter()
+                    .enumerate()
+                    .map(move |(j, (_, p))| (i + 1) * (j + 1) * p)
+            })
+            .sum()
+    }
+}
+
+pub enum Entry<'a, K, V> {
+    Occupied(&'a mut V),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+/// a key that isn't in the map yet, along with the box it would go in
+pub struct VacantEntry<'a, K, V> {
+    key: K,
+    lenses: &'a mut Vec<(K, V)>,
+    len: &'a mut usize,
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// adds the lens to the back of its box
+    pub fn insert(self, value: V) -> &'a mut V {
+        *self.len += 1;
+        self.lenses.push((self.key, value));
+        &mut self.lenses.last_mut().unwrap().1
+    }
+}
+
+impl<'a, K, V> Entry<'a, K, V> {
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with(self, default: impl FnOnce() -> V) -> &'a mut V {
+        match self {
+            Entry::Occupied(v) => v,
+            Entry::Vacant(vacant) => vacant.insert(default()),
+        }
+    }
+
+    pub fn and_modify(mut self, f: impl FnOnce(&mut V)) -> Self {
+        if let Entry::Occupied(v) = &mut self {
+            f(v);
+        }
+
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use shared::rng::XorShift;
+
+    use super::{Entry, LensMap};
+    use crate::ReindeerHash;
+
+    /// a key that hashes to its length
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct ByLen(&'static str);
+
+    impl ReindeerHash for ByLen {
+        fn hash(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    #[test]
+    fn sample() {
+        let mut map = LensMap::<_, _>::new();
+
+        for step in "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7".split(',') {
+            match step.split_once('=') {
+                Some((label, focal)) => {
+                    map.insert(label, focal.parse().unwrap());
+                }
+                None => {
+                    map.remove(&step[..step.len() - 1]);
+                }
+            }
+        }
+
+        assert_eq!(map.len(), 5);
+        assert_eq!(
+            map.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(),
+            vec![("rn", 1), ("cm", 2), ("ot", 7), ("ab", 5), ("pc", 6)]
+        );
+        assert_eq!(map.get("ot"), Some(&7));
+        assert_eq!(map.box_index("ot"), 3);
+        assert_eq!(map.focusing_power(), 145);
+    }
+
+    #[test]
+    fn entry() {
+        let mut map = LensMap::<_, _>::with_buckets(4);
+
+        *map.entry(ByLen("a")).or_insert(1) += 10;
+        map.entry(ByLen("a")).and_modify(|v| *v *= 2).or_insert(0);
+        map.entry(ByLen("bb")).and_modify(|v| *v *= 2).or_insert(3);
+
+        match map.entry(ByLen("ccc")) {
+            Entry::Vacant(vacant) => {
+                assert_eq!(vacant.key(), &ByLen("ccc"));
+                *vacant.insert(4) += 1;
+            }
+            Entry::Occupied(_) => panic!("ccc isn't in the map yet"),
+        }
+
+        assert_eq!(map.get(&ByLen("a")), Some(&22));
+        assert_eq!(map.get(&ByLen("bb")), Some(&3));
+        assert_eq!(map.boxes()[1], vec![(ByLen("a"), 22)]);
+        assert_eq!(map.boxes()[3], vec![(ByLen("ccc"), 5)]);
+        assert_eq!(map.len(), 3);
+    }
+
+    /// runs random operations against both maps with a handful of keys, so
+    /// that boxes collide often
+    #[test]
+    fn matches_hashmap() {
+        let mut rng = XorShift::default();
+        let mut next = move || rng.next_u64();
+
+        for buckets in [1, 3, 256] {
+            let mut lens_map = LensMap::<String, usize, str>::with_buckets(buckets);
+            let mut hash_map = HashMap::new();
+
+            for _ in 0..5_000 {
+                let key = format!("k{}", next() % 40);
+                let value = (next() % 10) as usize;
+
+                match next() % 4 {
+                    0 => assert_eq!(lens_map.remove(&key), hash_map.remove(&key)),
+                    1 => assert_eq!(lens_map.get(key.as_str()), hash_map.get(key.as_str())),
+                    2 => {
+                        *lens_map.entry(key.clone()).or_insert(0) += value;
+                        *hash_map.entry(key).or_insert(0) += value;
+                    }
+                    _ => assert_eq!(
+                        lens_map.insert(key.clone(), value),
+                        hash_map.insert(key, value)
+                    ),
+                }
+
+                assert_eq!(lens_map.len(), hash_map.len());
+            }
+
+            let mut lenses = lens_map
+                .iter()
+                .map(|(k, v)| (k.clone(), *v))
+                .collect::<Vec<_>>();
+            lenses.sort();
+
+            let mut expected = hash_map.into_iter().collect::<Vec<_>>();
+            expected.sort();
+
+            assert_eq!(lenses, expected);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::io::{ErrorKind, Read};
+
+use anyhow::{anyhow, bail, Result};
+
+use lens_map::LensMap;
+
+pub mod lens_map;
+pub mod trace;
+
+/// a single step of the initialization sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation<'a> {
+    /// `label=focal`
+    Insert(&'a str, usize),
+    /// `label-`
+    Remove(&'a str),
+}
+
+impl<'a> Operation<'a> {
+    /// parses a step, rejecting labels outside of ascii like
+    /// [`hash_sequence`] does, since the puzzle only hashes ascii codes
+    pub fn parse(step: &'a str) -> Result<Self> {
+        let operation = match step.strip_suffix('-') {
+            Some(label) => Operation::Remove(label),
+            None => {
+                let (label, focal) = step
+                    .split_once('=')
+                    .ok_or(anyhow!("the step {step:?} has no operation"))?;
+
+                Operation::Insert(label, focal.parse()?)
+            }
+        };
+
+        if !operation.label().is_ascii() {
+            bail!("the label in the step {step:?} is not ascii");
+        }
+
+        Ok(operation)
+    }
+
+    pub fn label(&self) -> &'a str {
+        match self {
+            Operation::Insert(label, _) | Operation::Remove(label) => label,
+        }
+    }
+
+    pub fn apply(&self, map: &mut LensMap<&'a str, usize>) {
+        match *self {
+            Operation::Insert(label, focal) => {
+                map.insert(label, focal);
+            }
+            Operation::Remove(label) => {
+                map.remove(label);
+            }
+        }
+    }
+}
+
+/// runs the initialization sequence, calling `observe` with every step, its
+/// box and the map right after the step
+pub fn initialize<'a>(
+    sequence: &'a str,
+    mut observe: impl FnMut(&'a str, usize, &LensMap<&'a str, usize>),
+) -> Result<LensMap<&'a str, usize>> {
+    let mut map = LensMap::new();
+
+    for step in sequence.trim().split(',') {
+        let operation = Operation::parse(step)?;
+        operation.apply(&mut map);
+
+        observe(step, map.box_index(operation.label()), &map);
+    }
+
+    Ok(map)
+}
+
+/// the HASH algorithm from the puzzle, which is only defined over ascii
+///
+/// hashing anything outside of ascii is a bug in the caller, caught by a
+/// debug assertion, so input has to be checked first like
+/// [`Operation::parse`] and [`hash_sequence`] do
+pub trait ReindeerHash {
+    fn hash(&self) -> usize;
+}
+
+/// the HASH algorithm run a few bytes at a time
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Hasher {
+    value: u8,
+}
+
+impl Hasher {
+    /// the bytes have to be ascii, see [`ReindeerHash`]
+    pub fn write(&mut self, bytes: &[u8]) {
+        debug_assert!(bytes.is_ascii(), "can't hash {bytes:?}, it isn't ascii");
+
+        for &b in bytes {
+            self.value = self.value.wrapping_add(b).wrapping_mul(17);
+        }
+    }
+
+    pub fn finish(&self) -> usize {
+        self.value as usize
+    }
+}
+
+impl ReindeerHash for [u8] {
+    fn hash(&self) -> usize {
+        let mut hasher = Hasher::default();
+        hasher.write(self);
+        hasher.finish()
+    }
+}
+
+impl ReindeerHash for str {
+    fn hash(&self) -> usize {
+        self.as_bytes().hash()
+    }
+}
+
+impl<T: ReindeerHash + ?Sized> ReindeerHash for &T {
+    fn hash(&self) -> usize {
+        (**self).hash()
+    }
+}
+
+impl ReindeerHash for String {
+    fn hash(&self) -> usize {
+        self.as_str().hash()
+    }
+}
+
+/// the sum of the hashes of every step in a comma separated sequence, read a
+/// chunk at a time so the sequence never has to fit in memory
+///
+/// newlines are ignored like in the puzzle, and any byte outside of ascii is
+/// an error since the puzzle only defines the hash over ascii codes
+pub fn hash_sequence(mut reader: impl Read) -> Result<usize> {
+    let mut buf = vec![0; 1 << 16];
+    let mut hasher = Hasher::default();
+    let mut sum = 0;
+    let mut offset = 0;
+
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        for &b in &buf[..n] {
+            match b {
+                b',' => {
+                    sum += hasher.finish();
+                    hasher = Hasher::default();
+                }
+                b'\n' | b'\r' => (),
+                _ if !b.is_ascii() => bail!("the byte {b:#04x} at offset {offset} is not ascii"),
+                _ => hasher.write(&[b]),
+            }
+
+            offset += 1;
+        }
+    }
+
+    Ok(sum + hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use crate::{hash_sequence, initialize, Operation, ReindeerHash};
+
+    #[test]
+    fn reindeerhash() {
+        assert_eq!("HASH".hash(), 52);
+        assert_eq!(b"HASH".hash(), 52);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "isn't ascii")]
+    fn non_ascii_step() {
+        "cé-".hash();
+    }
+
+    /// hands out one byte per read so every step crosses a chunk boundary
+    struct OneByte<'a>(&'a [u8]);
+
+    impl Read for OneByte<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let Some((&first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+
+            buf[0] = first;
+            self.0 = rest;
+
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn streaming() {
+        let sample = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,\not=9,ab=5,pc-,pc=6,ot=7\n";
+
+        assert_eq!(hash_sequence(sample.as_bytes()).unwrap(), 1320);
+
+        let mut reader = OneByte(sample.as_bytes());
+        assert_eq!(hash_sequence(&mut reader).unwrap(), 1320);
+
+        assert!(hash_sequence("rn=1,cé-".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn ascii_labels() {
+        assert_eq!(
+            Operation::parse("rn=1").unwrap(),
+            Operation::Insert("rn", 1)
+        );
+        assert_eq!(Operation::parse("cm-").unwrap(), Operation::Remove("cm"));
+        assert!(Operation::parse("cé-").is_err());
+        assert!(Operation::parse("é=3").is_err());
+        assert!(initialize("rn=1,cé=2", |_, _, _| ()).is_err());
+    }
+}
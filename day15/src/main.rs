@@ -1,74 +1,44 @@
+use std::{fs::File, io::BufReader};
+
+use anyhow::{anyhow, Result};
+use day15::{hash_sequence, initialize, trace::Trace};
+
 const INPUT: &str = include_str!("../input");
 
-fn main() {
-    let inp = INPUT.trim().split(',');
+fn main() -> Result<()> {
+    // `day15 trace [step]` prints the boxes after every step, or just one
+    // `day15 hash <file>` runs part 1 over a sequence streamed from a file
+    let mut args = std::env::args().skip(1);
+    let mode = args.next();
 
-    println!(
-        "part1: {}",
-        inp.clone().map(ReindeerHash::hash).sum::<usize>()
-    );
+    if mode.as_deref() == Some("hash") {
+        let path = args.next().ok_or(anyhow!("expected a file to hash"))?;
+        let file = BufReader::new(File::open(path)?);
 
-    let inp = inp.map(|ins| {
-        if ins.contains('-') {
-            (&ins[..ins.len() - 1], None)
-        } else {
-            (
-                &ins[..ins.len() - 2],
-                Some(ins[ins.len() - 1..].parse::<usize>().unwrap()),
-            )
-        }
-    });
+        println!("part1: {}", hash_sequence(file)?);
 
-    let mut map = vec![Vec::<(&str, usize)>::new(); 256];
+        return Ok(());
+    }
 
-    for (key, ins) in inp {
-        let map_idx = key.hash();
-        let position = map[map_idx].iter().position(|v| v.0 == key);
+    if mode.as_deref() == Some("trace") {
+        let trace = Trace::record(INPUT)?;
 
-        match ins {
-            // =
-            Some(label) => match position {
-                Some(p) => map[map_idx][p].1 = label,
-                None => map[map_idx].push((key, label)),
+        match args.next() {
+            Some(n) => match trace.display_step(n.parse()?) {
+                Some(step) => print!("{step}"),
+                None => println!("there are only {} steps", trace.len()),
             },
-            // -
-            None => {
-                if let Some(p) = position {
-                    map[map_idx].remove(p);
-                }
-            }
+            None => print!("{trace}"),
         }
-    }
-
-    let part2 = map
-        .into_iter()
-        .enumerate()
-        .flat_map(|(i, b)| {
-            b.into_iter()
-                .enumerate()
-                .map(move |(j, (_, p))| (i + 1) * (j + 1) * p)
-        })
-        .sum::<usize>();
 
-    println!("part2: {part2}");
-}
+        return Ok(());
+    }
 
-trait ReindeerHash {
-    fn hash(&self) -> usize;
-}
+    println!("part1: {}", hash_sequence(INPUT.as_bytes())?);
 
-impl ReindeerHash for str {
-    fn hash(&self) -> usize {
-        (self.chars().fold(0u8, |acc, c| acc * 17 + c as u8) * 17) as usize
-    }
-}
+    let map = initialize(INPUT, |_, _, _| ())?;
 
-#[cfg(test)]
-mod tests {
-    use crate::ReindeerHash;
+    println!("part2: {}", map.focusing_power());
 
-    #[test]
-    fn reindeerhash() {
-        assert_eq!("HASH".hash(), 52);
-    }
+    Ok(())
 }
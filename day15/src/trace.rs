@@ -0,0 +1,115 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use anyhow::Result;
+
+use crate::initialize;
+
+type Lenses<'a> = Vec<(&'a str, usize)>;
+
+/// the boxes after every step of an initialization sequence, recorded by
+/// running the sequence through [`initialize`]
+#[derive(Debug)]
+pub struct Trace<'a> {
+    /// each step with the box it touched and that box's lenses afterwards
+    steps: Vec<(&'a str, usize, Lenses<'a>)>,
+}
+
+impl<'a> Trace<'a> {
+    pub fn record(sequence: &'a str) -> Result<Self> {
+        let mut steps = vec![];
+
+        initialize(sequence, |step, b, map| {
+            steps.push((step, b, map.boxes()[b].clone()))
+        })?;
+
+        Ok(Self { steps })
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// the non empty boxes after the first `n` steps
+    pub fn state_after(&self, n: usize) -> BTreeMap<usize, Lenses<'a>> {
+        let mut boxes = BTreeMap::new();
+
+        for (_, b, lenses) in &self.steps[..n.min(self.steps.len())] {
+            boxes.insert(*b, lenses.clone());
+        }
+
+        boxes.retain(|_, lenses| !lenses.is_empty());
+        boxes
+    }
+
+    /// the boxes after step `n` (counting from 1) in the puzzle's format
+    pub fn display_step(&self, n: usize) -> Option<String> {
+        let (step, _, _) = self.steps.get(n.checked_sub(1)?)?;
+
+        let mut out = format!("After {step:?}:\n");
+
+        for (b, lenses) in self.state_after(n) {
+            out += &format!("Box {b}:");
+
+            for (label, focal) in lenses {
+                out += &format!(" [{label} {focal}]");
+            }
+
+            out += "\n";
+        }
+
+        Some(out)
+    }
+}
+
+impl Display for Trace<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for n in 1..=self.len() {
+            writeln!(f, "{}", self.display_step(n).unwrap())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Trace;
+
+    const SAMPLE: &str = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
+
+    #[test]
+    fn display() {
+        let trace = Trace::record(SAMPLE).unwrap();
+
+        assert_eq!(trace.len(), 11);
+        assert_eq!(
+            trace.display_step(1).unwrap(),
+            "After \"rn=1\":\nBox 0: [rn 1]\n"
+        );
+        assert_eq!(
+            trace.display_step(3).unwrap(),
+            "After \"qp=3\":\nBox 0: [rn 1]\nBox 1: [qp 3]\n"
+        );
+        assert_eq!(
+            trace.display_step(11).unwrap(),
+            "After \"ot=7\":\nBox 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]\n"
+        );
+        assert_eq!(trace.display_step(0), None);
+        assert_eq!(trace.display_step(12), None);
+    }
+
+    #[test]
+    fn state_after() {
+        let trace = Trace::record(SAMPLE).unwrap();
+
+        assert!(trace.state_after(0).is_empty());
+        assert_eq!(
+            trace.state_after(5).into_iter().collect::<Vec<_>>(),
+            vec![(0, vec![("rn", 1), ("cm", 2)])]
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    io::{self, Write},
+};
+
+use crate::{optics::Mirror, Bounce, Dir, Mirrors};
+
+/// every beam segment followed from a starting beam, along with the
+/// directions and number of beams that crossed each tile
+#[derive(Debug)]
+pub struct Beams<'a> {
+    mirrors: &'a Mirrors,
+    /// each beam as where it left from and the tiles it crossed
+    segments: Vec<(Bounce, Vec<(usize, usize)>)>,
+    directions: Vec<Vec<Dir>>,
+    crossings: Vec<usize>,
+}
+
+impl Mirrors {
+    pub fn trace(&self, starting_position: (usize, usize), starting_dir: Dir) -> Beams<'_> {
+        let mut segments = vec![];
+        let mut directions = vec![vec![]; self.mirrors.len()];
+        let mut crossings = vec![0; self.mirrors.len()];
+
+        let mut stack = vec![(starting_position, starting_dir)];
+        let mut visited = HashSet::<Bounce>::new();
+
+        while let Some((pos, dir)) = stack.pop() {
+            let (new_energized, next_bounces) = self.next_bounce(pos, dir);
+
+            for &(i, j) in &new_energized {
+                let tile = i * self.row_len + j;
+
+                crossings[tile] += 1;
+                if !directions[tile].contains(&dir) {
+                    directions[tile].push(dir);
+                }
+            }
+
+            segments.push(((pos, dir), new_energized));
+
+            next_bounces.for_each(|b| {
+                if visited.insert(b) {
+                    stack.push(b)
+                }
+            });
+        }
+
+        Beams {
+            mirrors: self,
+            segments,
+            directions,
+            crossings,
+        }
+    }
+}
+
+impl Beams<'_> {
+    pub fn energized(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.crossings
+            .iter()
+            .enumerate()
+            .filter(|(_, &c)| c > 0)
+            .map(|(t, _)| (t / self.mirrors.row_len, t % self.mirrors.row_len))
+    }
+
+    pub fn segments(&self) -> &[(Bounce, Vec<(usize, usize)>)] {
+        &self.segments
+    }
+
+    /// the directions the beams were going in when they crossed a tile
+    pub fn directions(&self, i: usize, j: usize) -> &[Dir] {
+        &self.directions[i * self.mirrors.row_len + j]
+    }
+
+    /// the puzzle's map of energized tiles, with `#` for energized tiles
+    pub fn energized_map(&self) -> String {
+        self.render(|tile, _| if self.crossings[tile] > 0 { '#' } else { '.' })
+    }
+
+    /// the puzzle's view of the beams, where empty tiles show the direction of
+    /// the beam crossing them, or how many directions if there's more than one
+    pub fn arrow_map(&self) -> String {
+        self.render(|tile, mirror| match &self.directions[tile][..] {
+            _ if !mirror.is_empty() => mirror.symbol(),
+            [] => mirror.symbol(),
+            [dir] => dir.arrow(),
+            dirs => char::from_digit(dirs.len() as u32, 10).unwrap(),
+        })
+    }
+
+    fn render(&self, mut tile: impl FnMut(usize, Mirror) -> char) -> String {
+        let mut out = String::new();
+
+        for (t, &mirror) in self.mirrors.mirrors.iter().enumerate() {
+            out.push(tile(t, mirror));
+
+            if t % self.mirrors.row_len == self.mirrors.row_len - 1 {
+                out.push('\n');
+            }
+        }
+
+        out
+    }
+
+    /// writes a binary ppm heatmap of how many beams crossed each tile, from
+    /// black for none through red to yellow for the most crossed tiles
+    pub fn write_ppm(&self, mut out: impl Write) -> io::Result<()> {
+        let max = self.crossings.iter().copied().max().unwrap_or(0).max(1);
+
+        write!(
+            out,
+            "P6\n{} {}\n255\n",
+            self.mirrors.row_len, self.mirrors.col_height
+        )?;
+
+        for &c in &self.crossings {
+            let heat = c * 511 / max;
+
+            let pixel = if c == 0 {
+                [0, 0, 0]
+            } else {
+                [heat.clamp(64, 255) as u8, heat.saturating_sub(256) as u8, 0]
+            };
+
+            out.write_all(&pixel)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Beams<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.arrow_map())
+    }
+}
+
+impl Dir {
+    pub fn arrow(self) -> char {
+        match self {
+            Dir::Up => '^',
+            Dir::Left => '<',
+            Dir::Right => '>',
+            Dir::Down => 'v',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Dir, Mirrors};
+
+    const SAMPLE: &str = r".|...\....
+|.-.\.....
+.....|-...
+........|.
+..........
+.........\
+..../.\\..
+.-.-/..|..
+.|....-|.\
+..//.|....";
+
+    #[test]
+    fn energized_map() {
+        let mirrors = SAMPLE.parse::<Mirrors>().unwrap();
+        let beams = mirrors.trace((0, usize::MAX), Dir::Right);
+
+        assert_eq!(
+            beams.energized_map(),
+            "######....
+.#...#....
+.#...#####
+.#...##...
+.#...##...
+.#...##...
+.#..####..
+########..
+.#######..
+.#...#.#..
+"
+        );
+        assert_eq!(beams.energized().count(), 46);
+    }
+
+    #[test]
+    fn arrow_map() {
+        let mirrors = SAMPLE.parse::<Mirrors>().unwrap();
+        let beams = mirrors.trace((0, usize::MAX), Dir::Right);
+
+        assert_eq!(
+            beams.arrow_map(),
+            r">|<<<\....
+|v-.\^....
+.v...|->>>
+.v...v^.|.
+.v...v^...
+.v...v^..\
+.v../2\\..
+<->-/vv|..
+.|<<<2-|.\
+.v//.|.v..
+"
+        );
+        assert_eq!(beams.directions(6, 5).len(), 2);
+        assert!(beams.directions(6, 5).contains(&Dir::Right));
+        assert!(beams.directions(6, 5).contains(&Dir::Down));
+        assert_eq!(
+            beams.segments()[0],
+            (((0, usize::MAX), Dir::Right), vec![(0, 0), (0, 1)])
+        );
+    }
+
+    #[test]
+    fn write_ppm() {
+        let mirrors = SAMPLE.parse::<Mirrors>().unwrap();
+        let mut ppm = vec![];
+
+        mirrors
+            .trace((0, usize::MAX), Dir::Right)
+            .write_ppm(&mut ppm)
+            .unwrap();
+
+        assert!(ppm.starts_with(b"P6\n10 10\n255\n"));
+        assert_eq!(ppm.len(), b"P6\n10 10\n255\n".len() + 10 * 10 * 3);
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use crate::{Bounce, Mirrors};
+
+/// every beam segment that can be reached from an edge of the grid, where a
+/// node is a beam leaving a position in a direction and its edges are the
+/// beams it turns into at the next mirror or splitter it hits
+///
+/// loops of beams are condensed into one component, and each component keeps
+/// a bitset of every tile energized by it and everything downstream of it
+#[derive(Debug)]
+pub struct BeamGraph {
+    nodes: HashMap<Bounce, usize>,
+    component: Vec<usize>,
+    energized: Vec<Vec<u64>>,
+}
+
+impl BeamGraph {
+    pub fn new(mirrors: &Mirrors) -> Self {
+        let mut nodes = HashMap::new();
+        let mut cells = vec![];
+        let mut edges = vec![];
+        let mut stack = mirrors.edge_starts();
+
+        for &start in &stack {
+            nodes.insert(start, nodes.len());
+        }
+
+        while let Some(bounce) = stack.pop() {
+            let (new_energized, next_bounces) = mirrors.next_bounce(bounce.0, bounce.1);
+
+            let next = next_bounces
+                .map(|b| {
+                    let id = nodes.len();
+
+                    *nodes.entry(b).or_insert_with(|| {
+                        stack.push(b);
+                        id
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let id = nodes[&bounce];
+            cells.resize(nodes.len(), vec![]);
+            edges.resize(nodes.len(), vec![]);
+
+            cells[id] = new_energized
+                .into_iter()
+                .map(|(i, j)| i * mirrors.row_len + j)
+                .collect::<Vec<_>>();
+            edges[id] = next;
+        }
+
+        cells.resize(nodes.len(), vec![]);
+        edges.resize(nodes.len(), vec![]);
+
+        let (component, components) = strongly_connected(&edges);
+
+        let words = (mirrors.row_len * mirrors.col_height).div_ceil(64);
+        let mut energized = vec![vec![0u64; words]; components.len()];
+
+        // components come out of tarjan's algorithm after everything they lead
+        // to, so each one only has to merge in finished bitsets
+        for (c, members) in components.iter().enumerate() {
+            let mut bits = vec![0u64; words];
+
+            for &node in members {
+                for &cell in &cells[node] {
+                    bits[cell / 64] |= 1 << (cell % 64);
+                }
+
+                for &next in &edges[node] {
+                    if component[next] != c {
+                        for (b, n) in bits.iter_mut().zip(&energized[component[next]]) {
+                            *b |= n;
+                        }
+                    }
+                }
+            }
+
+            energized[c] = bits;
+        }
+
+        Self {
+            nodes,
+            component,
+            energized,
+        }
+    }
+
+    /// the number of tiles energized by a beam starting just outside of the
+    /// grid, or `None` if that isn't one of the edge starts
+    pub fn count_energized(&self, start: Bounce) -> Option<usize> {
+        let node = self.nodes.get(&start)?;
+
+        Some(
+            self.energized[self.component[*node]]
+                .iter()
+                .map(|w| w.count_ones() as usize)
+                .sum(),
+        )
+    }
+}
+
+/// tarjan's algorithm without recursion, returning the component of every
+/// node and the nodes in each component, with components in reverse
+/// topological order
+fn strongly_connected(edges: &[Vec<usize>]) -> (Vec<usize>, Vec<Vec<usize>>) {
+    const UNVISITED: usize = usize::MAX;
+
+    let mut index = vec![UNVISITED; edges.len()];
+    let mut low = vec![0; edges.len()];
+    let mut on_stack = vec![false; edges.len()];
+    let mut stack = vec![];
+    let mut next_index = 0;
+
+    let mut component = vec![UNVISITED; edges.len()];
+    let mut components = vec![];
+
+    for root in 0..edges.len() {
+        if index[root] != UNVISITED {
+            continue;
+        }
+
+        let mut calls = vec![(root, 0)];
+
+        while let Some((node, child)) = calls.pop() {
+            if child == 0 {
+                index[node] = next_index;
+                low[node] = next_index;
+                next_index += 1;
+                stack.push(node);
+                on_stack[node] = true;
+            } else {
+                let prev = edges[node][child - 1];
+
+                if on_stack[prev] {
+                    low[node] = low[node].min(low[prev]);
+                }
+            }
+
+            if let Some(&next) = edges[node].get(child) {
+                calls.push((node, child + 1));
+
+                if index[next] == UNVISITED {
+                    calls.push((next, 0));
+                } else if on_stack[next] {
+                    low[node] = low[node].min(index[next]);
+                }
+
+                continue;
+            }
+
+            if low[node] == index[node] {
+                let mut members = vec![];
+
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[member] = false;
+                    component[member] = components.len();
+                    members.push(member);
+
+                    if member == node {
+                        break;
+                    }
+                }
+
+                components.push(members);
+            }
+        }
+    }
+
+    (component, components)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BeamGraph;
+    use crate::{Dir, Mirrors};
+
+    const SAMPLE: &str = r".|...\....
+|.-.\.....
+.....|-...
+........|.
+..........
+.........\
+..../.\\..
+.-.-/..|..
+.|....-|.\
+..//.|....";
+
+    #[test]
+    fn matches_count_energized() {
+        let mirrors = SAMPLE.parse::<Mirrors>().unwrap();
+        let graph = BeamGraph::new(&mirrors);
+
+        assert_eq!(
+            graph.count_energized(((0, usize::MAX), Dir::Right)),
+            Some(46)
+        );
+        assert_eq!(
+            graph.count_energized(((usize::MAX, 3), Dir::Down)),
+            Some(51)
+        );
+        assert_eq!(graph.count_energized(((3, 3), Dir::Down)), None);
+
+        for start in mirrors.edge_starts() {
+            assert_eq!(
+                graph.count_energized(start),
+                Some(mirrors.count_energized(start.0, start.1))
+            );
+        }
+    }
+}
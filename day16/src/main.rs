@@ -1,70 +1,83 @@
-use std::{collections::HashSet, fmt::Display, str::FromStr};
+use std::{fmt::Display, fs::File, io::BufWriter, str::FromStr};
 
 use anyhow::{anyhow, bail, Error, Result};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use shared::dprintln;
+
+use graph::BeamGraph;
+use optics::{Mirror, Optics};
+
+mod beams;
+mod graph;
+mod optics;
 
 const INPUT: &str = include_str!("../input");
 
 fn main() -> Result<()> {
-    let mirrors: Mirrors = INPUT.parse()?;
+    // `day16 extended <file>` solves a grid that can also use the extended
+    // optics, and `day16 map`, `day16 arrows` and `day16 heatmap <file.ppm>`
+    // show the beams from part 1 instead of solving
+    let mut args = std::env::args().skip(1);
+    let mode = args.next();
+
+    let mirrors = match mode.as_deref() {
+        Some("extended") => {
+            let path = args.next().ok_or(anyhow!("expected a grid to solve"))?;
+            Mirrors::parse_with(&std::fs::read_to_string(path)?, &Optics::extended())?
+        }
+        _ => INPUT.parse()?,
+    };
+
+    if let Some(mode) = mode.filter(|m| m != "extended") {
+        let beams = mirrors.trace((0, usize::MAX), Dir::Right);
+
+        match mode.as_str() {
+            "map" => print!("{}", beams.energized_map()),
+            "arrows" => print!("{beams}"),
+            "heatmap" => {
+                let path = args
+                    .next()
+                    .ok_or(anyhow!("expected a file for the heatmap"))?;
+                beams.write_ppm(BufWriter::new(File::create(path)?))?;
+            }
+            _ => bail!("unknown mode {mode}, expected extended, map, arrows or heatmap"),
+        }
+
+        return Ok(());
+    }
 
     let part1 = mirrors.count_energized((0, usize::MAX), Dir::Right);
 
     println!("part1: {part1}");
 
+    let graph = BeamGraph::new(&mirrors);
+
+    debug_assert_eq!(
+        graph.count_energized(((0, usize::MAX), Dir::Right)),
+        Some(part1)
+    );
+
     let max = mirrors
-        .get_column(0)
-        .map(|(i, _)| ((i, usize::MAX), Dir::Right))
-        .chain(
-            mirrors
-                .get_column(mirrors.row_len - 1)
-                .map(|(i, _)| ((i, mirrors.row_len), Dir::Left)),
-        )
-        .chain(
-            mirrors
-                .get_row(0)
-                .map(|(j, _)| ((usize::MAX, j), Dir::Down)),
-        )
-        .chain(
-            mirrors
-                .get_row(mirrors.col_height - 1)
-                .map(|(j, _)| ((mirrors.col_height, j), Dir::Up)),
-        )
-        .collect::<Vec<_>>()
-        .into_par_iter()
-        .map(|(starting_position, starting_dir)| {
-            mirrors.count_energized(starting_position, starting_dir)
-        })
-        .reduce(|| usize::MIN, usize::max);
+        .edge_starts()
+        .into_iter()
+        .filter_map(|start| graph.count_energized(start))
+        .max()
+        .unwrap_or(0);
 
     println!("part2: {max}");
 
     Ok(())
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Mirror {
-    /// `.`
-    None,
-    ///  `/`
-    ForwardDiagonal,
-    ///  \
-    BackwardsDiagonal,
-    ///  `-`
-    SplitterHorizontal,
-    ///  `|`
-    SplitterVertical,
-}
-
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
-enum Dir {
+pub enum Dir {
     Up,
     Left,
     Right,
     Down,
 }
 
+/// a beam leaving a position in a direction
+pub type Bounce = ((usize, usize), Dir);
+
 #[derive(Debug)]
 pub struct Mirrors {
     mirrors: Vec<Mirror>,
@@ -81,158 +94,60 @@ impl Mirrors {
         (0..self.row_len).map(move |j| (j, self.mirrors[i * self.row_len + j]))
     }
 
-    fn count_energized(&self, starting_position: (usize, usize), starting_dir: Dir) -> usize {
-        let mut energized = HashSet::new();
-
-        let mut stack = vec![(starting_position, starting_dir)];
-
-        let mut visited = HashSet::<((usize, usize), Dir)>::new();
-
-        while let Some((pos, dir)) = stack.pop() {
-            dprintln!("{pos:?} {dir:?}");
-            let (new_energized, next_bounces) = self.next_bounce(pos, dir);
-
-            for new in new_energized {
-                energized.insert(new);
-            }
-
-            next_bounces.for_each(|b| {
-                if !visited.contains(&b) {
-                    visited.insert(b);
-                    stack.push(b)
-                }
-            });
-        }
+    /// every beam entering the grid from just outside of an edge
+    fn edge_starts(&self) -> Vec<Bounce> {
+        self.get_column(0)
+            .map(|(i, _)| ((i, usize::MAX), Dir::Right))
+            .chain(
+                self.get_column(self.row_len - 1)
+                    .map(|(i, _)| ((i, self.row_len), Dir::Left)),
+            )
+            .chain(self.get_row(0).map(|(j, _)| ((usize::MAX, j), Dir::Down)))
+            .chain(
+                self.get_row(self.col_height - 1)
+                    .map(|(j, _)| ((self.col_height, j), Dir::Up)),
+            )
+            .collect()
+    }
 
-        energized.len()
+    fn count_energized(&self, starting_position: (usize, usize), starting_dir: Dir) -> usize {
+        self.trace(starting_position, starting_dir)
+            .energized()
+            .count()
     }
 
+    /// follows a beam from a position until it leaves the grid or reaches an
+    /// element that doesn't let it straight through, returning the tiles it
+    /// crossed and the beams that element sends out
     fn next_bounce(
         &self,
-        (i, j): (usize, usize),
+        (mut i, mut j): (usize, usize),
         direction: Dir,
-    ) -> (
-        Vec<(usize, usize)>,
-        impl Iterator<Item = ((usize, usize), Dir)>,
-    ) {
+    ) -> (Vec<(usize, usize)>, impl Iterator<Item = Bounce>) {
         let mut energized = vec![];
-        match direction {
-            Dir::Right => {
-                for (j, mirror) in self.get_row(i).skip(j + 1) {
-                    energized.push((i, j));
-
-                    match mirror {
-                        Mirror::None => {}
-                        Mirror::ForwardDiagonal => {
-                            return (energized, niter::one(((i, j), Dir::Up)))
-                        }
-                        Mirror::BackwardsDiagonal => {
-                            return (energized, niter::one(((i, j), Dir::Down)));
-                        }
-                        Mirror::SplitterHorizontal => {}
-                        Mirror::SplitterVertical => {
-                            return (
-                                energized,
-                                niter::two(((i, j), Dir::Up), ((i, j), Dir::Down)),
-                            )
-                        }
-                    }
-                }
-            }
-            Dir::Left => {
-                for (j, mirror) in self.get_row(i).rev().skip(self.row_len - j) {
-                    energized.push((i, j));
-
-                    match mirror {
-                        Mirror::None => {}
-                        Mirror::ForwardDiagonal => {
-                            return (energized, niter::one(((i, j), Dir::Down)))
-                        }
-                        Mirror::BackwardsDiagonal => {
-                            return (energized, niter::one(((i, j), Dir::Up)));
-                        }
-                        Mirror::SplitterHorizontal => {}
-                        Mirror::SplitterVertical => {
-                            return (
-                                energized,
-                                niter::two(((i, j), Dir::Up), ((i, j), Dir::Down)),
-                            )
-                        }
-                    }
-                }
-            }
-            Dir::Down => {
-                for (i, mirror) in self.get_column(j).skip(i + 1) {
-                    energized.push((i, j));
-
-                    match mirror {
-                        Mirror::None => {}
-                        Mirror::ForwardDiagonal => {
-                            return (energized, niter::one(((i, j), Dir::Left)))
-                        }
-                        Mirror::BackwardsDiagonal => {
-                            return (energized, niter::one(((i, j), Dir::Right)));
-                        }
-                        Mirror::SplitterVertical => {}
-                        Mirror::SplitterHorizontal => {
-                            return (
-                                energized,
-                                niter::two(((i, j), Dir::Left), ((i, j), Dir::Right)),
-                            )
-                        }
-                    }
-                }
-            }
-            Dir::Up => {
-                for (i, mirror) in self.get_column(j).rev().skip(self.col_height - i) {
-                    energized.push((i, j));
-
-                    match mirror {
-                        Mirror::None => {}
-                        Mirror::ForwardDiagonal => {
-                            return (energized, niter::one(((i, j), Dir::Right)))
-                        }
-                        Mirror::BackwardsDiagonal => {
-                            return (energized, niter::one(((i, j), Dir::Left)));
-                        }
-                        Mirror::SplitterVertical => {}
-                        Mirror::SplitterHorizontal => {
-                            return (
-                                energized,
-                                niter::two(((i, j), Dir::Left), ((i, j), Dir::Right)),
-                            )
-                        }
-                    }
-                }
-            }
-        };
 
-        (energized, niter::zero())
-    }
-}
-
-mod niter {
-    use std::{array::IntoIter, iter::Flatten};
+        let outputs = loop {
+            (i, j) = direction.step((i, j));
 
-    type Iter<T> = Flatten<IntoIter<Option<T>, 2>>;
+            if i >= self.col_height || j >= self.row_len {
+                break 0;
+            }
 
-    pub fn zero<T>() -> Iter<T> {
-        [None, None].into_iter().flatten()
-    }
+            energized.push((i, j));
 
-    pub fn one<T>(elem: T) -> Iter<T> {
-        [Some(elem), None].into_iter().flatten()
-    }
+            let mirror = self.mirrors[i * self.row_len + j];
+            if !mirror.passes(direction) {
+                break mirror.outputs(direction);
+            }
+        };
 
-    pub fn two<T>(a: T, b: T) -> Iter<T> {
-        [Some(a), Some(b)].into_iter().flatten()
+        (energized, Dir::from_bits(outputs).map(move |d| ((i, j), d)))
     }
 }
 
-impl FromStr for Mirrors {
-    type Err = Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Mirrors {
+    /// parses a grid made of the given optics
+    fn parse_with(s: &str, optics: &Optics) -> Result<Self> {
         let row_len = s
             .lines()
             .next()
@@ -244,8 +159,14 @@ impl FromStr for Mirrors {
 
         let data = s
             .lines()
-            .flat_map(|l| l.chars().map(TryFrom::try_from))
-            .collect::<Result<Vec<_>, _>>()?;
+            .flat_map(|l| {
+                l.chars().map(|c| {
+                    optics
+                        .get(c)
+                        .ok_or(anyhow!("{c} is not an optical element"))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
 
         Ok(Self {
             mirrors: data,
@@ -255,6 +176,14 @@ impl FromStr for Mirrors {
     }
 }
 
+impl FromStr for Mirrors {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_with(s, &Optics::standard())
+    }
+}
+
 impl Display for Mirrors {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for (i, mirror) in self.mirrors.iter().enumerate() {
@@ -268,30 +197,3 @@ impl Display for Mirrors {
         Ok(())
     }
 }
-
-impl TryFrom<char> for Mirror {
-    type Error = Error;
-
-    fn try_from(value: char) -> Result<Self, Self::Error> {
-        match value {
-            '.' => Ok(Mirror::None),
-            '/' => Ok(Mirror::ForwardDiagonal),
-            '\\' => Ok(Mirror::BackwardsDiagonal),
-            '-' => Ok(Mirror::SplitterHorizontal),
-            '|' => Ok(Mirror::SplitterVertical),
-            _ => bail!("not a mirror"),
-        }
-    }
-}
-
-impl Display for Mirror {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Mirror::None => write!(f, "."),
-            Mirror::ForwardDiagonal => write!(f, "/"),
-            Mirror::BackwardsDiagonal => write!(f, "\\"),
-            Mirror::SplitterHorizontal => write!(f, "-"),
-            Mirror::SplitterVertical => write!(f, "|"),
-        }
-    }
-}
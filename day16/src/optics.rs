@@ -0,0 +1,218 @@
+use std::fmt::Display;
+
+use crate::Dir;
+
+/// an optical element, as the directions a beam can leave it in for each
+/// direction a beam can come into it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mirror {
+    symbol: char,
+    /// a bitset of outgoing directions, indexed by the incoming direction
+    outputs: [u8; 4],
+}
+
+impl Mirror {
+    /// an element that turns a beam going in each direction into beams going
+    /// in the listed directions, absorbing beams going in any direction that
+    /// isn't listed
+    pub fn new(symbol: char, table: &[(Dir, &[Dir])]) -> Self {
+        let mut outputs = [0; 4];
+
+        for (incoming, outgoing) in table {
+            outputs[*incoming as usize] = outgoing.iter().fold(0, |bits, d| bits | d.bit());
+        }
+
+        Self { symbol, outputs }
+    }
+
+    /// an element every beam goes straight through
+    pub fn empty(symbol: char) -> Self {
+        Self {
+            symbol,
+            outputs: Dir::ALL.map(Dir::bit),
+        }
+    }
+
+    pub fn symbol(&self) -> char {
+        self.symbol
+    }
+
+    pub fn is_empty(&self) -> bool {
+        Dir::ALL.iter().all(|&d| self.passes(d))
+    }
+
+    /// whether a beam going in `dir` carries on unchanged
+    pub fn passes(&self, dir: Dir) -> bool {
+        self.outputs[dir as usize] == dir.bit()
+    }
+
+    pub fn outputs(&self, dir: Dir) -> u8 {
+        self.outputs[dir as usize]
+    }
+}
+
+impl Display for Mirror {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol)
+    }
+}
+
+/// the elements a grid can be made of, looked up by their symbol
+#[derive(Debug, Clone)]
+pub struct Optics {
+    elements: Vec<Mirror>,
+}
+
+impl Optics {
+    /// the puzzle's `.`, `/`, `\`, `-` and `|`
+    pub fn standard() -> Self {
+        use Dir::*;
+
+        Self { elements: vec![] }
+            .with(Mirror::empty('.'))
+            .with(Mirror::new(
+                '/',
+                &[
+                    (Up, &[Right]),
+                    (Left, &[Down]),
+                    (Right, &[Up]),
+                    (Down, &[Left]),
+                ],
+            ))
+            .with(Mirror::new(
+                '\\',
+                &[
+                    (Up, &[Left]),
+                    (Left, &[Up]),
+                    (Right, &[Down]),
+                    (Down, &[Right]),
+                ],
+            ))
+            .with(Mirror::new(
+                '-',
+                &[
+                    (Up, &[Left, Right]),
+                    (Left, &[Left]),
+                    (Right, &[Right]),
+                    (Down, &[Left, Right]),
+                ],
+            ))
+            .with(Mirror::new(
+                '|',
+                &[
+                    (Up, &[Up]),
+                    (Left, &[Up, Down]),
+                    (Right, &[Up, Down]),
+                    (Down, &[Down]),
+                ],
+            ))
+    }
+
+    /// the standard elements, plus `#` which absorbs every beam, `+` which
+    /// splits a beam three ways, and `^`, `<`, `>` and `v` which only let
+    /// beams through in the direction they point
+    pub fn extended() -> Self {
+        let mut optics = Self::standard().with(Mirror::new('#', &[])).with(Mirror {
+            symbol: '+',
+            outputs: Dir::ALL.map(|d| d.bit() | d.turn_left().bit() | d.turn_right().bit()),
+        });
+
+        for d in Dir::ALL {
+            optics = optics.with(Mirror::new(d.arrow(), &[(d, &[d])]));
+        }
+
+        optics
+    }
+
+    /// adds an element, replacing any element with the same symbol
+    pub fn with(mut self, mirror: Mirror) -> Self {
+        self.elements.retain(|m| m.symbol != mirror.symbol);
+        self.elements.push(mirror);
+        self
+    }
+
+    pub fn get(&self, symbol: char) -> Option<Mirror> {
+        self.elements.iter().find(|m| m.symbol == symbol).copied()
+    }
+}
+
+impl Dir {
+    pub const ALL: [Dir; 4] = [Dir::Up, Dir::Left, Dir::Right, Dir::Down];
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+
+    /// the directions in a bitset of outgoing directions
+    pub fn from_bits(bits: u8) -> impl Iterator<Item = Dir> {
+        Dir::ALL.into_iter().filter(move |d| bits & d.bit() != 0)
+    }
+
+    pub fn turn_left(self) -> Dir {
+        match self {
+            Dir::Up => Dir::Left,
+            Dir::Left => Dir::Down,
+            Dir::Down => Dir::Right,
+            Dir::Right => Dir::Up,
+        }
+    }
+
+    pub fn turn_right(self) -> Dir {
+        self.turn_left().turn_left().turn_left()
+    }
+
+    /// the next position in this direction, wrapping around so that a
+    /// position just outside the grid is `usize::MAX` or past the last tile
+    pub fn step(self, (i, j): (usize, usize)) -> (usize, usize) {
+        match self {
+            Dir::Up => (i.wrapping_sub(1), j),
+            Dir::Left => (i, j.wrapping_sub(1)),
+            Dir::Right => (i, j.wrapping_add(1)),
+            Dir::Down => (i.wrapping_add(1), j),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Mirror, Optics};
+    use crate::{Dir, Mirrors};
+
+    #[test]
+    fn standard_tables() {
+        let optics = Optics::standard();
+
+        let splitter = optics.get('-').unwrap();
+        assert!(splitter.passes(Dir::Left));
+        assert_eq!(
+            Dir::from_bits(splitter.outputs(Dir::Down)).collect::<Vec<_>>(),
+            vec![Dir::Left, Dir::Right]
+        );
+
+        assert!(optics.get('.').unwrap().is_empty());
+        assert!(!optics.get('/').unwrap().is_empty());
+        assert_eq!(optics.get('#'), None);
+    }
+
+    #[test]
+    fn extended_elements() {
+        let optics = Optics::extended();
+
+        // the absorber stops the beam from reaching the splitter
+        let mirrors = Mirrors::parse_with("..\\\n...\n..#\n.+|", &optics).unwrap();
+        assert_eq!(mirrors.count_energized((0, usize::MAX), Dir::Right), 5);
+
+        let mirrors = Mirrors::parse_with(".<..", &optics).unwrap();
+        assert_eq!(mirrors.count_energized((0, usize::MAX), Dir::Right), 2);
+        assert_eq!(mirrors.count_energized((0, 4), Dir::Left), 4);
+
+        let mirrors = Mirrors::parse_with("...\n.+.\n...", &optics).unwrap();
+        assert_eq!(mirrors.count_energized((1, usize::MAX), Dir::Right), 5);
+
+        let optics = optics.with(Mirror::new('.', &[]));
+        let mirrors = Mirrors::parse_with("...", &optics).unwrap();
+        assert_eq!(mirrors.count_energized((0, usize::MAX), Dir::Right), 1);
+
+        assert!(Mirrors::parse_with("..+", &Optics::standard()).is_err());
+    }
+}
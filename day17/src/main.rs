@@ -1,72 +1,186 @@
-use std::{
-    collections::{BinaryHeap, HashMap},
-    ops::RangeBounds,
-    str::FromStr,
-};
+use std::{collections::BinaryHeap, fs::File, io::BufWriter, ops::RangeInclusive};
 
-const INPUT: &str = include_str!("../sample");
+use anyhow::{anyhow, bail, Result};
 
-fn main() {
-    let inp = INPUT
+use route::{Route, Step};
+
+mod route;
+
+const INPUT: &str = include_str!("../input");
+
+/// how far a crucible can go in a straight line before it has to turn
+const CRUCIBLE: RangeInclusive<usize> = 1..=3;
+/// an ultra crucible also has to go 4 blocks before it can turn or stop
+const ULTRA_CRUCIBLE: RangeInclusive<usize> = 4..=10;
+
+fn main() -> Result<()> {
+    let inp = parse(INPUT);
+
+    // `day17 route [ultra]` shows the best route for a crucible, and
+    // `day17 svg <file.svg> [ultra]` draws it
+    let mut args = std::env::args().skip(1);
+    if let Some(mode) = args.next() {
+        let path = match mode.as_str() {
+            "route" => None,
+            "svg" => Some(args.next().ok_or(anyhow!("expected a file for the svg"))?),
+            _ => bail!("unknown mode {mode}, expected route or svg"),
+        };
+
+        let runs = match args.next().as_deref() {
+            Some("ultra") => ULTRA_CRUCIBLE,
+            _ => CRUCIBLE,
+        };
+
+        let route = min_heat_loss(&inp, runs).ok_or(anyhow!("there's no route"))?;
+
+        match path {
+            Some(path) => route.write_svg(&inp, BufWriter::new(File::create(path)?))?,
+            None => {
+                print!("{}", route.render(&inp));
+
+                for step in route.steps() {
+                    println!("{step}");
+                }
+            }
+        }
+
+        return Ok(());
+    }
+
+    let part1 = min_heat_loss(&inp, CRUCIBLE).ok_or(anyhow!("the crucible can't stop"))?;
+
+    println!("part1: {}", part1.heat_loss());
+
+    match min_heat_loss(&inp, ULTRA_CRUCIBLE) {
+        Some(part2) => println!("part2: {}", part2.heat_loss()),
+        None => println!("part2: the ultra crucible can't stop at the bottom right"),
+    }
+
+    Ok(())
+}
+
+fn parse(input: &str) -> Vec<Vec<u32>> {
+    input
         .lines()
         .map(str::chars)
         .map(|c| c.filter_map(|c| c.to_digit(10)).collect())
-        .collect::<Vec<Vec<_>>>();
+        .collect()
+}
 
-    let target_i = inp.len() - 1;
-    let target_j = inp[0].len() - 1;
+/// the least heat lost getting from the top left to the bottom right, going
+/// straight for a number of blocks in `runs` between each turn, or `None` if
+/// the crucible can't stop at the bottom right
+///
+/// this is an a* search over every block, direction and run length, which
+/// guesses the rest of the way as the cheapest block in the city for each
+/// block left to go, so it never guesses too high
+fn min_heat_loss(inp: &[Vec<u32>], runs: RangeInclusive<usize>) -> Option<Route> {
+    let rows = inp.len();
+    let cols = inp[0].len();
 
-    let mut visited = vec![vec![(u32::MAX, None); inp[0].len()]; inp.len()];
+    let cheapest = inp.iter().flatten().copied().min().unwrap_or(0);
+    let estimate = |i: usize, j: usize| (rows - 1 - i + cols - 1 - j) as u32 * cheapest;
+
+    let states = States {
+        cols,
+        lengths: runs.end() + 1,
+    };
+    let state = |node: &Node| states.encode(node.i, node.j, node.direction, node.length);
+
+    let mut best = vec![u32::MAX; states.count(rows)];
+    // the state each state was best reached from, to follow the route back
+    let mut prev = vec![usize::MAX; best.len()];
 
     let mut nodes = BinaryHeap::with_capacity(10_000);
 
-    nodes.push(Node::new(0, 0, 0, 0, Dir::Right, None));
-    nodes.push(Node::new(0, 0, 0, 0, Dir::Down, None));
+    for dir in [Dir::Right, Dir::Down] {
+        let node = Node::new(0, 0, 0, 0, dir).with_estimate(estimate(0, 0));
+
+        best[state(&node)] = 0;
+        nodes.push(node);
+    }
 
     while let Some(node) = nodes.pop() {
-        // if !(0..=target_i).contains(&node.i)
-        //     || !(0..=target_j).contains(&node.j)
-        //     || visited[node.i][node.j] <= node.heat_loss
-        // {
-        //     continue;
-        // }
-
-        let next_heat_loss = node.heat_loss + inp[node.i][node.j];
-
-        visited[node.i][node.j] = (
-            next_heat_loss,
-            node.prev.map(|prev| (prev.0, prev.1, node.direction)),
-        );
+        if node.heat_loss > best[state(&node)] {
+            continue;
+        }
 
-        if node.i == target_i && node.j == target_j {
-            break;
+        if node.i == rows - 1
+            && node.j == cols - 1
+            && (node.length == 0 || runs.contains(&node.length))
+        {
+            return Some(follow_back(state(&node), &prev, &best, states));
         }
 
-        let prev = Some((node.i, node.j));
+        for (i, j, length, dir) in node.get_next(&runs) {
+            if i >= rows || j >= cols {
+                continue;
+            }
 
-        for (i, j, length, dir) in node.get_next() {
-            if (0..=target_i).contains(&i)
-                && (0..=target_j).contains(&j)
-                && visited[i][j].0 >= next_heat_loss
-            {
-                nodes.push(Node::new(i, j, next_heat_loss, length, dir, prev));
+            let next = Node::new(i, j, node.heat_loss + inp[i][j], length, dir)
+                .with_estimate(estimate(i, j));
+
+            if next.heat_loss < best[state(&next)] {
+                best[state(&next)] = next.heat_loss;
+                prev[state(&next)] = state(&node);
+                nodes.push(next);
             }
         }
     }
 
-    let heat_loss = visited[target_i][target_j].0;
+    None
+}
 
-    for v in visited {
-        for (d, p) in v {
-            match p {
-                Some((i, j, dir)) => print!("{d:3} {i:2},{j:2},{:5}|", format!("{dir:?}")),
-                None => print!("{d:3} {:11}|", ' '),
-            }
-        }
-        println!()
+/// the route into a state, from the states each one was reached from
+fn follow_back(mut state: usize, prev: &[usize], best: &[u32], states: States) -> Route {
+    let mut steps = vec![];
+
+    while prev[state] != usize::MAX {
+        let (i, j, direction, _) = states.decode(state);
+
+        steps.push(Step {
+            i,
+            j,
+            direction,
+            heat_loss: best[state],
+        });
+
+        state = prev[state];
     }
 
-    println!("part1 {heat_loss}");
+    steps.reverse();
+
+    Route::new(steps)
+}
+
+/// numbers every block, direction and run length, so the search can keep
+/// its states in flat vectors
+#[derive(Debug, Clone, Copy)]
+struct States {
+    cols: usize,
+    /// one more than the longest run
+    lengths: usize,
+}
+
+impl States {
+    /// how many states there are in a city with `rows` rows
+    fn count(&self, rows: usize) -> usize {
+        rows * self.cols * Dir::ALL.len() * self.lengths
+    }
+
+    fn encode(&self, i: usize, j: usize, direction: Dir, length: usize) -> usize {
+        ((i * self.cols + j) * Dir::ALL.len() + direction.index()) * self.lengths + length
+    }
+
+    /// the block, direction and run length of a state from [`States::encode`]
+    fn decode(&self, state: usize) -> (usize, usize, Dir, usize) {
+        let length = state % self.lengths;
+        let state = state / self.lengths;
+        let direction = Dir::ALL[state % Dir::ALL.len()];
+        let block = state / Dir::ALL.len();
+
+        (block / self.cols, block % self.cols, direction, length)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
@@ -76,49 +190,59 @@ struct Node {
     j: usize,
     length: usize,
     direction: Dir,
-    prev: Option<(usize, usize)>,
+    /// a guess at the heat still to be lost, which is never too high
+    estimate: u32,
 }
 
 impl Node {
-    pub fn new(
-        i: usize,
-        j: usize,
-        heat_loss: u32,
-        length: usize,
-        direction: Dir,
-        prev: Option<(usize, usize)>,
-    ) -> Self {
+    pub fn new(i: usize, j: usize, heat_loss: u32, length: usize, direction: Dir) -> Self {
         Self {
             heat_loss,
             i,
             j,
             length,
             direction,
-            prev,
+            estimate: 0,
         }
     }
 
-    pub fn get_next(mut self) -> impl Iterator<Item = (usize, usize, usize, Dir)> {
+    pub fn with_estimate(mut self, estimate: u32) -> Self {
+        self.estimate = estimate;
+        self
+    }
+
+    /// the blocks the crucible can move to next, where `length` is how far
+    /// it has gone in a straight line and the start can turn either way
+    ///
+    /// positions off the top or left of the grid wrap around to `usize::MAX`
+    pub fn get_next(
+        self,
+        runs: &RangeInclusive<usize>,
+    ) -> impl Iterator<Item = (usize, usize, usize, Dir)> {
+        let can_turn = self.length == 0 || self.length >= *runs.start();
+        let (i, j) = (self.i, self.j);
+
         match self.direction {
             Dir::Left | Dir::Right => [
-                Some((self.i - 1, self.j, 0, Dir::Up)),
-                Some((self.i + 1, self.j, 0, Dir::Down)),
+                Some((i.wrapping_sub(1), j, 1, Dir::Up)),
+                Some((i + 1, j, 1, Dir::Down)),
             ],
             Dir::Up | Dir::Down => [
-                Some((self.i, self.j - 1, 0, Dir::Left)),
-                Some((self.i, self.j + 1, 0, Dir::Right)),
+                Some((i, j.wrapping_sub(1), 1, Dir::Left)),
+                Some((i, j + 1, 1, Dir::Right)),
             ],
         }
+        .map(|next| next.filter(|_| can_turn))
         .into_iter()
-        .chain([(self.length < 3).then(|| {
-            match self.direction {
-                Dir::Left => self.j -= 1,
-                Dir::Right => self.j += 1,
-                Dir::Up => self.i -= 1,
-                Dir::Down => self.i += 1,
-            }
+        .chain([(self.length < *runs.end()).then(|| {
+            let (i, j) = match self.direction {
+                Dir::Left => (i, j.wrapping_sub(1)),
+                Dir::Right => (i, j + 1),
+                Dir::Up => (i.wrapping_sub(1), j),
+                Dir::Down => (i + 1, j),
+            };
 
-            (self.i, self.j, self.length + 1, self.direction)
+            (i, j, self.length + 1, self.direction)
         })])
         .flatten()
     }
@@ -126,7 +250,13 @@ impl Node {
 
 #[cfg(test)]
 mod tests {
-    use crate::{Dir, Node};
+    use std::ops::RangeInclusive;
+
+    use shared::rng::XorShift;
+
+    use crate::{min_heat_loss, parse, Dir, Node, States, CRUCIBLE, ULTRA_CRUCIBLE};
+
+    const SAMPLE: &str = include_str!("../sample2");
 
     #[test]
     fn get_next() {
@@ -136,17 +266,162 @@ mod tests {
                 (11, 10, 1, Dir::Down),
                 (10, 11, 2, Dir::Right),
             ],
-            Node::new(10, 10, 10, 1, Dir::Right, None)
-                .get_next()
+            Node::new(10, 10, 10, 1, Dir::Right)
+                .get_next(&CRUCIBLE)
                 .collect::<Vec<_>>()
         );
 
         assert_eq!(
             vec![(9, 10, 1, Dir::Up), (11, 10, 1, Dir::Down),],
-            Node::new(10, 10, 10, 3, Dir::Right, None)
-                .get_next()
+            Node::new(10, 10, 10, 3, Dir::Right)
+                .get_next(&CRUCIBLE)
                 .collect::<Vec<_>>()
         );
+
+        assert_eq!(
+            vec![(10, 11, 4, Dir::Right)],
+            Node::new(10, 10, 10, 3, Dir::Right)
+                .get_next(&ULTRA_CRUCIBLE)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn states() {
+        let states = States {
+            cols: 7,
+            lengths: 11,
+        };
+        let mut seen = vec![false; states.count(5)];
+
+        for (i, j, dir, length) in (0..5).flat_map(|i| {
+            (0..7).flat_map(move |j| {
+                Dir::ALL
+                    .into_iter()
+                    .flat_map(move |dir| (0..11).map(move |length| (i, j, dir, length)))
+            })
+        }) {
+            let state = states.encode(i, j, dir, length);
+
+            assert!(!seen[state]);
+            seen[state] = true;
+            assert_eq!(states.decode(state), (i, j, dir, length));
+        }
+
+        assert!(seen.into_iter().all(|s| s));
+    }
+
+    #[test]
+    fn sample() {
+        assert_eq!(
+            min_heat_loss(&parse(SAMPLE), CRUCIBLE).map(|r| r.heat_loss()),
+            Some(102)
+        );
+        assert_eq!(
+            min_heat_loss(&parse(SAMPLE), ULTRA_CRUCIBLE).map(|r| r.heat_loss()),
+            Some(94)
+        );
+
+        let unfortunate =
+            parse("111111111111\n999999999991\n999999999991\n999999999991\n999999999991");
+        assert_eq!(
+            min_heat_loss(&unfortunate, ULTRA_CRUCIBLE).map(|r| r.heat_loss()),
+            Some(71)
+        );
+
+        assert_eq!(min_heat_loss(&parse("11\n11"), ULTRA_CRUCIBLE), None);
+    }
+
+    /// tries every way of going straight then turning from the top left,
+    /// never ending a run on a block it already ended a run on going the same
+    /// way, since that would only lose more heat
+    fn brute_force(inp: &[Vec<u32>], runs: &RangeInclusive<usize>) -> Option<u32> {
+        fn go(
+            inp: &[Vec<u32>],
+            runs: &RangeInclusive<usize>,
+            (i, j): (usize, usize),
+            vertical: bool,
+            heat_loss: u32,
+            path: &mut Vec<(usize, usize, bool)>,
+            best: &mut Option<u32>,
+        ) {
+            if best.is_some_and(|b| heat_loss >= b) || path.contains(&(i, j, vertical)) {
+                return;
+            }
+
+            if (i, j) == (inp.len() - 1, inp[0].len() - 1) && !path.is_empty() {
+                *best = Some(heat_loss);
+                return;
+            }
+
+            path.push((i, j, vertical));
+
+            let (di, dj) = if vertical { (0, 1) } else { (1, 0) };
+
+            for sign in [1, -1] {
+                let mut heat_loss = heat_loss;
+
+                for length in 1..=*runs.end() {
+                    let i = i as isize + sign * di * length as isize;
+                    let j = j as isize + sign * dj * length as isize;
+
+                    let Some(&block) = inp
+                        .get(i as usize)
+                        .and_then(|row| row.get(j as usize))
+                        .filter(|_| i >= 0 && j >= 0)
+                    else {
+                        break;
+                    };
+
+                    heat_loss += block;
+
+                    if runs.contains(&length) {
+                        go(
+                            inp,
+                            runs,
+                            (i as usize, j as usize),
+                            !vertical,
+                            heat_loss,
+                            path,
+                            best,
+                        );
+                    }
+                }
+            }
+
+            path.pop();
+        }
+
+        let mut best = None;
+
+        for vertical in [false, true] {
+            go(inp, runs, (0, 0), vertical, 0, &mut vec![], &mut best);
+        }
+
+        best
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut rng = XorShift::default();
+        let mut next = move || rng.next_u64();
+
+        for _ in 0..300 {
+            let rows = 2 + (next() % 5) as usize;
+            let cols = 2 + (next() % 5) as usize;
+
+            let inp = (0..rows)
+                .map(|_| (0..cols).map(|_| 1 + (next() % 9) as u32).collect())
+                .collect::<Vec<Vec<_>>>();
+
+            for runs in [CRUCIBLE, ULTRA_CRUCIBLE, 2..=3] {
+                assert_eq!(
+                    min_heat_loss(&inp, runs.clone()).map(|r| r.heat_loss()),
+                    brute_force(&inp, &runs),
+                    "{inp:?} {runs:?}"
+                );
+            }
+        }
     }
 }
 
@@ -158,14 +433,23 @@ impl PartialOrd for Node {
 
 impl Ord for Node {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        other.heat_loss.cmp(&self.heat_loss)
+        (other.heat_loss + other.estimate).cmp(&(self.heat_loss + self.estimate))
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Dir {
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dir {
     Left,
     Right,
     Up,
     Down,
 }
+
+impl Dir {
+    /// every direction, in the order of [`Dir::index`]
+    pub const ALL: [Dir; 4] = [Dir::Left, Dir::Right, Dir::Up, Dir::Down];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
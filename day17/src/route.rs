@@ -0,0 +1,168 @@
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
+use crate::Dir;
+
+/// one move of a route: the block moved into, which way the crucible went to
+/// get there, and the heat lost so far including that block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Step {
+    pub i: usize,
+    pub j: usize,
+    pub direction: Dir,
+    pub heat_loss: u32,
+}
+
+/// the blocks a crucible goes through from the top left, not counting the
+/// top left block itself since it loses no heat there
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    steps: Vec<Step>,
+}
+
+impl Route {
+    pub fn new(steps: Vec<Step>) -> Self {
+        Self { steps }
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    pub fn heat_loss(&self) -> u32 {
+        self.steps.last().map_or(0, |s| s.heat_loss)
+    }
+
+    /// the city as in the puzzle, with an arrow on every block the route
+    /// moves into
+    pub fn render(&self, inp: &[Vec<u32>]) -> String {
+        let mut rows = inp
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|&b| char::from_digit(b, 10).unwrap_or('?'))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        for step in &self.steps {
+            rows[step.i][step.j] = step.direction.arrow();
+        }
+
+        rows.into_iter()
+            .map(|row| row.into_iter().chain(['\n']).collect::<String>())
+            .collect()
+    }
+
+    /// writes the city as an svg, shading blocks darker the more heat they
+    /// lose, with the route drawn over the top
+    pub fn write_svg(&self, inp: &[Vec<u32>], mut out: impl Write) -> io::Result<()> {
+        const BLOCK: usize = 20;
+
+        let width = inp.first().map_or(0, Vec::len) * BLOCK;
+        let height = inp.len() * BLOCK;
+
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        )?;
+        writeln!(out, "<title>heat loss {}</title>", self.heat_loss())?;
+
+        for (i, row) in inp.iter().enumerate() {
+            for (j, &block) in row.iter().enumerate() {
+                let shade = 255 - block * 20;
+
+                writeln!(
+                    out,
+                    r#"<rect x="{}" y="{}" width="{BLOCK}" height="{BLOCK}" fill="rgb({shade},{shade},{shade})"/>"#,
+                    j * BLOCK,
+                    i * BLOCK
+                )?;
+                writeln!(
+                    out,
+                    r#"<text x="{}" y="{}" font-size="12" text-anchor="middle" fill="gray">{block}</text>"#,
+                    j * BLOCK + BLOCK / 2,
+                    i * BLOCK + BLOCK * 3 / 4
+                )?;
+            }
+        }
+
+        let points = [(0, 0)]
+            .into_iter()
+            .chain(self.steps.iter().map(|s| (s.i, s.j)))
+            .map(|(i, j)| format!("{},{}", j * BLOCK + BLOCK / 2, i * BLOCK + BLOCK / 2))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        writeln!(
+            out,
+            r#"<polyline points="{points}" fill="none" stroke="red" stroke-width="3" stroke-linejoin="round"/>"#
+        )?;
+        writeln!(out, "</svg>")
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} to {},{} ({} lost)",
+            self.direction.arrow(),
+            self.i,
+            self.j,
+            self.heat_loss
+        )
+    }
+}
+
+impl Dir {
+    pub fn arrow(self) -> char {
+        match self {
+            Dir::Left => '<',
+            Dir::Right => '>',
+            Dir::Up => '^',
+            Dir::Down => 'v',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{min_heat_loss, parse, CRUCIBLE};
+
+    #[test]
+    fn render() {
+        let inp = parse(include_str!("../sample2"));
+        let route = min_heat_loss(&inp, CRUCIBLE).unwrap();
+
+        assert_eq!(route.heat_loss(), 102);
+        assert_eq!(route.steps().iter().map(|s| s.heat_loss).max(), Some(102));
+        assert!(route
+            .steps()
+            .windows(2)
+            .all(|w| w[0].heat_loss + inp[w[1].i][w[1].j] == w[1].heat_loss));
+
+        // there's more than one best route, so only check the parts every
+        // route has to share
+        let map = route.render(&inp);
+        assert_eq!(map.lines().count(), 13);
+        assert!(map.starts_with('2'));
+        assert!(map.ends_with("v\n") || map.ends_with(">\n"));
+    }
+
+    #[test]
+    fn write_svg() {
+        let inp = parse("12\n34");
+        let route = min_heat_loss(&inp, CRUCIBLE).unwrap();
+        let mut svg = vec![];
+
+        route.write_svg(&inp, &mut svg).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+
+        assert_eq!(route.render(&inp), "1>\n3v\n");
+        assert!(svg.contains(r#"<polyline points="10,10 30,10 30,30""#));
+        assert!(svg.contains("<title>heat loss 6</title>"));
+    }
+}
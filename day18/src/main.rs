@@ -1,254 +1,137 @@
-use std::collections::BinaryHeap;
+use std::{fs::File, io::BufWriter};
 
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use shared::dprintln;
 
-const INPUT: &str = include_str!("../input");
-
-fn main() -> Result<()> {
-    let part1 = handle_input(|l| {
-        let mut c = l.split_whitespace();
+use plan::DigPlan;
+use render::Drawing;
 
-        (
-            c.next().unwrap().chars().next().unwrap(),
-            c.next().unwrap().parse::<i64>().unwrap(),
-        )
-    })?;
+mod plan;
+mod render;
 
-    println!("part1: {part1}");
-
-    let part2 = handle_input(|l| {
-        let mut n = l.split_whitespace().rev();
+const INPUT: &str = include_str!("../input");
 
-        let i = n.next().unwrap();
-        let i = &i[2..i.len() - 1].split_at(5);
+fn main() -> Result<()> {
+    // `day18 draw <file.svg|file.png> [scale]` draws the part 1 lagoon, and
+    // `day18 draw2 <file> <scale>` draws the part 2 one shrunk down
+    let mut args = std::env::args().skip(1);
+    if let Some(mode @ ("draw" | "draw2")) = args.next().as_deref() {
+        let path = args.next().ok_or(anyhow!("expected a file to draw to"))?;
+        let scale = match (mode, args.next()) {
+            (_, Some(scale)) => scale.parse()?,
+            ("draw", None) => 1,
+            // part 2 lagoons are over a million blocks across
+            _ => bail!("expected how much to scale the part 2 lagoon down by"),
+        };
 
-        let d = match i.1 {
-            "0" => 'R',
-            "1" => 'D',
-            "2" => 'L',
-            "3" => 'U',
-            _ => unreachable!(),
+        let get_dir_length = match mode {
+            "draw" => part1_dir_length as fn(&str) -> (char, i64),
+            _ => part2_dir_length,
         };
 
-        (d, i64::from_str_radix(i.0, 16).unwrap())
-    })?;
+        let plan = DigPlan::parse(INPUT, get_dir_length)?;
+        let drawing = Drawing::new(&plan, INPUT, scale)?;
+        let out = BufWriter::new(File::create(&path)?);
 
-    println!("part2: {part2}");
+        if path.ends_with(".png") {
+            drawing.write_png(out)?;
+        } else {
+            drawing.write_svg(out)?;
+        }
 
-    Ok(())
-}
+        return Ok(());
+    }
 
-fn handle_input(get_dir_length: impl Fn(&str) -> (char, i64)) -> Result<usize> {
-    let mut horizontal_lines = BinaryHeap::new();
-    let mut lines = vec![];
-
-    let mut points = INPUT
-        .lines()
-        .map(get_dir_length)
-        .scan((0, 0), |curr, (d, l)| {
-            match d {
-                'R' => curr.1 += l,
-                'L' => curr.1 -= l,
-                'U' => curr.0 -= l,
-                'D' => curr.0 += l,
-                _ => panic!(),
+    // `day18 union` digs plans that overlap or cross themselves instead of
+    // rejecting them
+    if std::env::args().nth(1).as_deref() == Some("union") {
+        for (part, get_dir_length) in [
+            (1, part1_dir_length as fn(&str) -> (char, i64)),
+            (2, part2_dir_length),
+        ] {
+            let plan = DigPlan::parse(INPUT, get_dir_length)?;
+
+            for problem in plan.problems() {
+                println!("{problem}");
             }
 
-            Some((*curr, l))
-        })
-        .chain([((0, 0), 0)]);
+            println!("part{part}: {}", plan.union_area());
+        }
 
-    let mut sum = 0;
+        return Ok(());
+    }
 
-    let first = points.next().unwrap();
-    let mut prev = first.0;
+    let part1 = handle_input(INPUT, part1_dir_length)?;
 
-    let mut min = (0, 0);
-    let mut max = (0, 0);
+    println!("part1: {part1}");
 
-    for (next, l) in points.chain([first]) {
-        sum += l;
-        if min.0 > next.0 {
-            min.0 = next.0;
-        }
-        if min.1 > next.1 {
-            min.1 = next.1;
-        }
-        if max.0 < next.0 {
-            max.0 = next.0;
-        }
-        if max.1 < next.1 {
-            max.1 = next.1;
-        }
+    let part2 = handle_input(INPUT, part2_dir_length)?;
 
-        dprintln!("p|{prev:?}|{next:?}");
-        if prev.0 == next.0 {
-            // if not already in the heap
-            if horizontal_lines.iter().all(|&l| l != next.0) {
-                horizontal_lines.push(next.0)
-            }
-        }
+    println!("part2: {part2}");
 
-        if let Some(line) = Line::from_points(prev, next) {
-            lines.push(line);
-        };
+    Ok(())
+}
 
-        prev = next;
-    }
+fn part1_dir_length(l: &str) -> (char, i64) {
+    let mut c = l.split_whitespace();
 
-    // println!("{min:?} {max:?}");
-    // for i in min.0..=max.0 {
-    //     for j in min.1..=max.1 {
-    //         if lines.iter().any(|l| l.contains_i(i) && l.contains_j(j)) {
-    //             print!("#")
-    //         } else {
-    //             print!(".")
-    //         }
-    //     }
-    //
-    //     println!()
-    // }
-
-    let mut sum = sum as usize;
-
-    println!("sum: {sum}");
-
-    lines.sort_unstable_by_key(|l| match l {
-        Line::Horizontal { i: _, j1, j2: _ } => *j1,
-        Line::Vertical { i1: _, i2: _, j } => *j,
-    });
-
-    #[cfg(debug_assertions)]
-    for line in lines.iter() {
-        println!("{line:?}")
-    }
+    (
+        c.next().unwrap().chars().next().unwrap(),
+        c.next().unwrap().parse::<i64>().unwrap(),
+    )
+}
 
-    let horizontal_lines = horizontal_lines.into_sorted_vec();
-    dprintln!("{horizontal_lines:?}");
-
-    for w in horizontal_lines.windows(2) {
-        let prev_line = w[0];
-        let line = w[1];
-        // no horizontal lines part
-        if line - prev_line != 1 {
-            let rlines = lines
-                .iter()
-                .filter(|l| l.contains_i(prev_line + 1))
-                .collect::<Vec<_>>();
-
-            let to_check = rlines
-                .into_iter()
-                .scan((false, 0), |state, l| match l {
-                    Line::Horizontal { i: _, j1: _, j2: _ } => unreachable!(),
-                    Line::Vertical { i1, i2: _, j } => {
-                        let ret = if state.0 {
-                            Some(state.1..*j)
-                        } else {
-                            Some(0..0)
-                        };
-
-                        if line != *i1 {
-                            state.0 = !state.0;
-                        }
-                        state.1 = *j + 1;
-
-                        ret
-                    }
-                })
-                .flatten()
-                .count();
-
-            // gets all the possible positions
-            let num = (line - prev_line - 1) as usize * to_check;
-
-            dprintln!("{prev_line}-{line}: {to_check} * {}", line - prev_line - 1);
-
-            sum += num;
-        }
+fn part2_dir_length(l: &str) -> (char, i64) {
+    let mut n = l.split_whitespace().rev();
 
-        // where the line is
-        let rlines = lines
-            .iter()
-            .filter(|l| l.contains_i(line))
-            .collect::<Vec<_>>();
-
-        let num = rlines
-            .into_iter()
-            .scan((false, 0), |state, l| match l {
-                Line::Horizontal { i: _, j1, j2 } => {
-                    let og = state.1;
-                    state.1 = *j2 + 1;
-
-                    Some(og..*j1)
-                }
-                Line::Vertical { i1, i2: _, j } => {
-                    let ret = if state.0 {
-                        Some(state.1..*j)
-                    } else {
-                        Some(0..0)
-                    };
-
-                    if line != *i1 {
-                        state.0 = !state.0;
-                    }
-                    state.1 = *j + 1;
-
-                    ret
-                }
-            })
-            .flatten()
-            .count();
-
-        dprintln!("{line}: {num}");
-
-        sum += num;
-    }
+    let i = n.next().unwrap();
+    let i = &i[2..i.len() - 1].split_at(5);
 
-    Ok(sum)
-}
+    let d = match i.1 {
+        "0" => 'R',
+        "1" => 'D',
+        "2" => 'L',
+        "3" => 'U',
+        _ => unreachable!(),
+    };
 
-#[derive(Debug, Clone, Copy)]
-enum Line {
-    Horizontal { i: i64, j1: i64, j2: i64 },
-    Vertical { i1: i64, i2: i64, j: i64 },
+    (d, i64::from_str_radix(i.0, 16).unwrap())
 }
 
-impl Line {
-    pub fn from_points(p1: (i64, i64), p2: (i64, i64)) -> Option<Self> {
-        if p1.0 == p2.0 {
-            let j2 = p1.1.max(p2.1);
-            let j1 = p1.1.min(p2.1);
-            if j2 - j1 <= 1 {
-                None
-            } else {
-                Some(Line::Horizontal {
-                    i: p1.0,
-                    j1: j1 + 1,
-                    j2: j2 - 1,
-                })
-            }
-        } else {
-            assert_eq!(p1.1, p2.1);
-            Some(Line::Vertical {
-                j: p1.1,
-                i1: p1.0.min(p2.0),
-                i2: p1.0.max(p2.0),
-            })
-        }
-    }
+fn handle_input(input: &str, get_dir_length: impl Fn(&str) -> (char, i64)) -> Result<i64> {
+    let plan = DigPlan::parse(input, get_dir_length)?;
+    let lagoon = plan.validate()?;
 
-    pub fn contains_i(&self, i_val: i64) -> bool {
-        match self {
-            Line::Horizontal { i, j1: _, j2: _ } => *i == i_val,
-            Line::Vertical { i1, i2, j: _ } => (*i1..=*i2).contains(&i_val),
-        }
-    }
+    dprintln!("{:?}", lagoon.vertices());
 
-    pub fn contains_j(&self, j_val: i64) -> bool {
-        match self {
-            Line::Horizontal { i: _, j1, j2 } => (*j1..=*j2).contains(&j_val),
-            Line::Vertical { i1: _, i2: _, j } => j_val == *j,
-        }
+    Ok(lagoon.trench_area())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{handle_input, part1_dir_length, part2_dir_length};
+
+    pub const SAMPLE: &str = "R 6 (#70c710)
+D 5 (#0dc571)
+L 2 (#5713f0)
+D 2 (#d2c081)
+R 2 (#59c680)
+D 2 (#411b91)
+L 5 (#8ceee2)
+U 2 (#caa173)
+L 1 (#1b58a2)
+U 2 (#caa171)
+R 2 (#7807d2)
+U 3 (#a77fa3)
+L 2 (#015232)
+U 2 (#7a21e3)";
+
+    #[test]
+    fn sample() {
+        assert_eq!(handle_input(SAMPLE, part1_dir_length).unwrap(), 62);
+        assert_eq!(
+            handle_input(SAMPLE, part2_dir_length).unwrap(),
+            952408144115
+        );
     }
 }
@@ -0,0 +1,314 @@
+use std::fmt::Display;
+
+use anyhow::{bail, Result};
+use shared::polygon::Polygon;
+
+/// a dig plan as the step and length of each instruction, dug from `(0, 0)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigPlan {
+    moves: Vec<((i64, i64), i64)>,
+}
+
+/// where an instruction starts and ends
+pub type Edge = ((i64, i64), (i64, i64));
+
+/// something that stops a dig plan from being a simple loop, with the
+/// indices of the instructions involved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    /// the plan finishes somewhere other than where it started
+    NotClosed { end: (i64, i64) },
+    /// an instruction that doesn't dig anything
+    Empty { index: usize },
+    /// an instruction goes straight back along the one before it
+    Backtrack { first: usize, second: usize },
+    /// two instructions dig along the same line over the same ground
+    Overlap { first: usize, second: usize },
+    /// two instructions meet somewhere other than the corner between them
+    Cross { first: usize, second: usize },
+}
+
+impl DigPlan {
+    pub fn parse(input: &str, get_dir_length: impl Fn(&str) -> (char, i64)) -> Result<Self> {
+        let moves = input
+            .lines()
+            .map(get_dir_length)
+            .map(|(d, l)| {
+                let step = match d {
+                    'R' => (0, 1),
+                    'L' => (0, -1),
+                    'U' => (-1, 0),
+                    'D' => (1, 0),
+                    _ => bail!("{d} is not a direction"),
+                };
+
+                Ok((step, l))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { moves })
+    }
+
+    /// where each instruction starts and ends
+    pub fn edges(&self) -> Vec<Edge> {
+        self.moves
+            .iter()
+            .scan((0, 0), |curr, &(step, length)| {
+                let start = *curr;
+                curr.0 += step.0 * length;
+                curr.1 += step.1 * length;
+
+                Some((start, *curr))
+            })
+            .collect()
+    }
+
+    /// every way the plan isn't a simple loop, checking each pair of
+    /// instructions
+    pub fn problems(&self) -> Vec<Problem> {
+        let edges = self.edges();
+        let n = edges.len();
+        let mut problems = vec![];
+
+        if let Some(&(_, end)) = edges.last() {
+            if end != (0, 0) {
+                problems.push(Problem::NotClosed { end });
+            }
+        }
+
+        for (index, &(_, length)) in self.moves.iter().enumerate() {
+            if length <= 0 {
+                problems.push(Problem::Empty { index });
+            }
+        }
+
+        for first in 0..n {
+            for second in first + 1..n {
+                let (a, b) = (edges[first], edges[second]);
+                let (step_a, step_b) = (self.moves[first].0, self.moves[second].0);
+
+                let Some(shared) = intersection(a, b) else {
+                    continue;
+                };
+
+                let parallel = step_a.0 * step_b.1 == step_a.1 * step_b.0;
+
+                // the point one instruction hands over to the other, if they
+                // follow on from each other
+                let corner = if second == first + 1 {
+                    Some(a.1)
+                } else if first == 0 && second == n - 1 && b.1 == a.0 {
+                    Some(a.0)
+                } else {
+                    None
+                };
+
+                if corner.is_some() && step_a == (-step_b.0, -step_b.1) {
+                    problems.push(Problem::Backtrack { first, second });
+                } else if corner == Some(shared.0) && shared.0 == shared.1 {
+                    continue;
+                } else if parallel {
+                    problems.push(Problem::Overlap { first, second });
+                } else {
+                    problems.push(Problem::Cross { first, second });
+                }
+            }
+        }
+
+        problems
+    }
+
+    /// the plan as a polygon, as long as it's a simple loop
+    pub fn validate(&self) -> Result<Polygon<i64>> {
+        if let Some(problem) = self.problems().first() {
+            bail!("{problem}");
+        }
+
+        Ok(Polygon::<i64>::from_moves(self.moves.iter().copied()))
+    }
+
+    /// the number of blocks dug out, counting blocks dug more than once only
+    /// once and digging out everything the trench closes off
+    ///
+    /// this squashes the plan down to the rows and columns where the trench
+    /// starts or stops, marks the trench, then flood fills in from the
+    /// outside, so it works on plans that overlap or cross themselves
+    pub fn union_area(&self) -> i64 {
+        let edges = self.edges();
+
+        // every block is either trench or not between two of these
+        let breaks = |coord: fn((i64, i64)) -> i64| {
+            let mut breaks = edges
+                .iter()
+                .flat_map(|&(a, b)| [coord(a), coord(a) + 1, coord(b), coord(b) + 1])
+                .chain([0, 1])
+                .collect::<Vec<_>>();
+            breaks.sort_unstable();
+            breaks.dedup();
+
+            // a row or column of nothing all the way round to flood fill from
+            let first = breaks[0] - 1;
+            let last = breaks[breaks.len() - 1] + 1;
+            breaks.insert(0, first);
+            breaks.push(last);
+
+            breaks
+        };
+
+        let rows = breaks(|p| p.0);
+        let cols = breaks(|p| p.1);
+        let index = |breaks: &[i64], c: i64| breaks.binary_search(&c).unwrap();
+
+        let (height, width) = (rows.len() - 1, cols.len() - 1);
+        let mut trench = vec![false; height * width];
+
+        for &(a, b) in &edges {
+            for i in index(&rows, a.0.min(b.0))..index(&rows, a.0.max(b.0) + 1) {
+                for j in index(&cols, a.1.min(b.1))..index(&cols, a.1.max(b.1) + 1) {
+                    trench[i * width + j] = true;
+                }
+            }
+        }
+
+        let mut outside = vec![false; height * width];
+        let mut stack = vec![(0usize, 0usize)];
+        outside[0] = true;
+
+        while let Some((i, j)) = stack.pop() {
+            let neighbours = [
+                (i.wrapping_sub(1), j),
+                (i + 1, j),
+                (i, j.wrapping_sub(1)),
+                (i, j + 1),
+            ];
+
+            for (i, j) in neighbours {
+                if i < height && j < width && !trench[i * width + j] && !outside[i * width + j] {
+                    outside[i * width + j] = true;
+                    stack.push((i, j));
+                }
+            }
+        }
+
+        (0..height)
+            .flat_map(|i| (0..width).map(move |j| (i, j)))
+            .filter(|&(i, j)| !outside[i * width + j])
+            .map(|(i, j)| (rows[i + 1] - rows[i]) * (cols[j + 1] - cols[j]))
+            .sum()
+    }
+}
+
+/// the stretch two horizontal or vertical edges have in common, as the
+/// corners of the rectangle they share
+fn intersection(a: Edge, b: Edge) -> Option<Edge> {
+    let low = (
+        a.0 .0.min(a.1 .0).max(b.0 .0.min(b.1 .0)),
+        a.0 .1.min(a.1 .1).max(b.0 .1.min(b.1 .1)),
+    );
+    let high = (
+        a.0 .0.max(a.1 .0).min(b.0 .0.max(b.1 .0)),
+        a.0 .1.max(a.1 .1).min(b.0 .1.max(b.1 .1)),
+    );
+
+    (low.0 <= high.0 && low.1 <= high.1).then_some((low, high))
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::NotClosed { end } => {
+                write!(f, "the plan ends at {end:?} instead of where it started")
+            }
+            Problem::Empty { index } => write!(f, "instruction {index} doesn't dig anything"),
+            Problem::Backtrack { first, second } => {
+                write!(
+                    f,
+                    "instruction {second} goes back along instruction {first}"
+                )
+            }
+            Problem::Overlap { first, second } => {
+                write!(f, "instructions {first} and {second} dig the same ground")
+            }
+            Problem::Cross { first, second } => {
+                write!(f, "instructions {first} and {second} cross")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DigPlan, Problem};
+    use crate::{part1_dir_length, part2_dir_length, tests::SAMPLE};
+
+    fn plan(moves: &str) -> DigPlan {
+        DigPlan::parse(&moves.replace(", ", "\n"), part1_dir_length).unwrap()
+    }
+
+    #[test]
+    fn sample() {
+        for get_dir_length in [part1_dir_length, part2_dir_length] {
+            let plan = DigPlan::parse(SAMPLE, get_dir_length).unwrap();
+
+            assert_eq!(plan.problems(), vec![]);
+            assert_eq!(plan.union_area(), plan.validate().unwrap().trench_area());
+        }
+    }
+
+    #[test]
+    fn problems() {
+        assert_eq!(
+            plan("R 2, D 2, L 2").problems(),
+            vec![Problem::NotClosed { end: (2, 0) }]
+        );
+        assert_eq!(
+            plan("R 3, L 3").problems(),
+            vec![Problem::Backtrack {
+                first: 0,
+                second: 1
+            },]
+        );
+        assert_eq!(
+            plan("R 2, D 2, R 2, D 2, L 2, U 2, L 2, U 2").problems(),
+            vec![
+                Problem::Overlap {
+                    first: 1,
+                    second: 5
+                },
+                Problem::Cross {
+                    first: 1,
+                    second: 6
+                },
+                Problem::Cross {
+                    first: 2,
+                    second: 5
+                },
+                Problem::Overlap {
+                    first: 2,
+                    second: 6
+                },
+            ]
+        );
+        assert!(plan("R 2, D 2, L 2, U 2, R 2, D 2, L 2, U 2")
+            .problems()
+            .contains(&Problem::Overlap {
+                first: 0,
+                second: 4
+            }));
+        assert!(plan("R 2, D 0, L 2").validate().is_err());
+    }
+
+    #[test]
+    fn union_area() {
+        assert_eq!(plan("R 3, L 3").union_area(), 4);
+        assert_eq!(
+            plan("R 2, D 2, L 2, U 2, R 2, D 2, L 2, U 2").union_area(),
+            9
+        );
+        // two squares touching at a corner block
+        assert_eq!(
+            plan("R 2, D 2, R 2, D 2, L 2, U 2, L 2, U 2").union_area(),
+            17
+        );
+    }
+}
@@ -0,0 +1,323 @@
+use std::{
+    io::{self, Write},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail, Error, Result};
+
+use crate::plan::{DigPlan, Edge};
+
+/// the colour in brackets at the end of each instruction, like `(#70c710)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color([u8; 3]);
+
+/// what the inside of the lagoon is filled with
+const INTERIOR: Color = Color([0xd0, 0xd0, 0xd0]);
+const BACKGROUND: Color = Color([0xff, 0xff, 0xff]);
+/// the widest or tallest a png is drawn
+const MAX_SIDE: usize = 4096;
+
+impl FromStr for Color {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let hex = s
+            .strip_prefix("(#")
+            .and_then(|s| s.strip_suffix(')'))
+            .filter(|hex| hex.len() == 6)
+            .ok_or(anyhow!("{s} is not a colour like (#rrggbb)"))?;
+
+        let mut rgb = [0; 3];
+        for (c, i) in rgb.iter_mut().zip((0..6).step_by(2)) {
+            *c = u8::from_str_radix(&hex[i..i + 2], 16)?;
+        }
+
+        Ok(Self(rgb))
+    }
+}
+
+/// a dig plan with the colour of every instruction, shrunk down by `scale`
+#[derive(Debug)]
+pub struct Drawing<'a> {
+    plan: &'a DigPlan,
+    colors: Vec<Color>,
+    scale: i64,
+}
+
+impl<'a> Drawing<'a> {
+    /// takes the colours from the last part of each line of the input
+    pub fn new(plan: &'a DigPlan, input: &str, scale: i64) -> Result<Self> {
+        if scale < 1 {
+            bail!("can't scale a drawing down by {scale}");
+        }
+
+        let colors = input
+            .lines()
+            .map(|l| {
+                l.split_whitespace()
+                    .last()
+                    .ok_or(anyhow!("no colour on {l}"))?
+                    .parse()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if colors.len() != plan.edges().len() {
+            bail!("there should be a colour for every instruction");
+        }
+
+        Ok(Self {
+            plan,
+            colors,
+            scale,
+        })
+    }
+
+    /// every edge as blocks in the drawing, with the top left block at `(0, 0)`
+    fn scaled_edges(&self) -> (Vec<Edge>, (i64, i64)) {
+        let edges = self.plan.edges();
+
+        let min = edges
+            .iter()
+            .fold((0, 0), |min, &(a, _)| (min.0.min(a.0), min.1.min(a.1)));
+        let scale = |p: (i64, i64)| {
+            (
+                (p.0 - min.0).div_euclid(self.scale),
+                (p.1 - min.1).div_euclid(self.scale),
+            )
+        };
+
+        let scaled = edges
+            .into_iter()
+            .map(|(a, b)| (scale(a), scale(b)))
+            .collect::<Vec<_>>();
+
+        let size = scaled.iter().fold((1, 1), |size, &(a, b)| {
+            (size.0.max(a.0.max(b.0) + 1), size.1.max(a.1.max(b.1) + 1))
+        });
+
+        (scaled, size)
+    }
+
+    /// the lagoon with the middle of each block of trench on whole numbers,
+    /// the inside filled in and each edge drawn in its own colour
+    pub fn write_svg(&self, mut out: impl Write) -> io::Result<()> {
+        let (edges, (height, width)) = self.scaled_edges();
+
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="-0.5 -0.5 {width} {height}">"#
+        )?;
+
+        let points = edges
+            .iter()
+            .map(|((i, j), _)| format!("{j},{i}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        writeln!(
+            out,
+            r#"<polygon points="{points}" fill="{}"/>"#,
+            INTERIOR.hex()
+        )?;
+
+        for (((i1, j1), (i2, j2)), color) in edges.iter().zip(&self.colors) {
+            writeln!(
+                out,
+                r#"<line x1="{j1}" y1="{i1}" x2="{j2}" y2="{i2}" stroke="{}" stroke-width="1" stroke-linecap="square"/>"#,
+                color.hex()
+            )?;
+        }
+
+        writeln!(out, "</svg>")
+    }
+
+    /// the lagoon as a picture with a pixel for every block, or every
+    /// `scale` by `scale` blocks, as long as it's no more than [`MAX_SIDE`]
+    /// pixels each way
+    pub fn pixels(&self) -> Result<(usize, usize, Vec<Color>)> {
+        let (edges, (height, width)) = self.scaled_edges();
+        let (height, width) = (height as usize, width as usize);
+
+        if height.max(width) > MAX_SIDE {
+            bail!(
+                "a {width} by {height} picture is too big to draw, try scaling it down by {}",
+                self.scale * height.max(width).div_ceil(MAX_SIDE) as i64
+            );
+        }
+
+        let mut pixels = vec![None; height * width];
+
+        for (&(a, b), &color) in edges.iter().zip(&self.colors) {
+            for i in a.0.min(b.0)..=a.0.max(b.0) {
+                for j in a.1.min(b.1)..=a.1.max(b.1) {
+                    pixels[i as usize * width + j as usize] = Some(color);
+                }
+            }
+        }
+
+        // flood fill the outside from every pixel around the edge, so
+        // whatever's left is inside
+        let mut stack = (0..height)
+            .flat_map(|i| [(i, 0), (i, width - 1)])
+            .chain((0..width).flat_map(|j| [(0, j), (height - 1, j)]))
+            .collect::<Vec<_>>();
+
+        while let Some((i, j)) = stack.pop() {
+            if i >= height || j >= width || pixels[i * width + j].is_some() {
+                continue;
+            }
+
+            pixels[i * width + j] = Some(BACKGROUND);
+            stack.extend([
+                (i.wrapping_sub(1), j),
+                (i + 1, j),
+                (i, j.wrapping_sub(1)),
+                (i, j + 1),
+            ]);
+        }
+
+        let pixels = pixels.into_iter().map(|p| p.unwrap_or(INTERIOR)).collect();
+
+        Ok((width, height, pixels))
+    }
+
+    /// writes the pixels as an uncompressed png
+    pub fn write_png(&self, mut out: impl Write) -> Result<()> {
+        let (width, height, pixels) = self.pixels()?;
+
+        // each row starts with the "no filter" filter
+        let raw = pixels
+            .chunks(width)
+            .flat_map(|row| [0].into_iter().chain(row.iter().flat_map(|c| c.0)))
+            .collect::<Vec<_>>();
+
+        let mut ihdr = vec![];
+        ihdr.extend((width as u32).to_be_bytes());
+        ihdr.extend((height as u32).to_be_bytes());
+        // 8 bit rgb, with the only compression, filter and interlace methods
+        ihdr.extend([8, 2, 0, 0, 0]);
+
+        out.write_all(b"\x89PNG\r\n\x1a\n")?;
+        write_chunk(&mut out, b"IHDR", &ihdr)?;
+        write_chunk(&mut out, b"IDAT", &zlib_stored(&raw))?;
+        write_chunk(&mut out, b"IEND", &[])?;
+
+        Ok(())
+    }
+}
+
+impl Color {
+    fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0[0], self.0[1], self.0[2])
+    }
+}
+
+fn write_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    out.write_all(&crc32(kind.iter().chain(data)).to_be_bytes())
+}
+
+/// a zlib stream made of deflate blocks that aren't compressed at all
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+
+        out.push(blocks.peek().is_none() as u8);
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    out.extend(((b << 16) | a).to_be_bytes());
+
+    out
+}
+
+fn crc32<'a>(data: impl IntoIterator<Item = &'a u8>) -> u32 {
+    !data.into_iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc32, Color, Drawing, BACKGROUND, INTERIOR};
+    use crate::{part1_dir_length, part2_dir_length, plan::DigPlan, tests::SAMPLE};
+
+    #[test]
+    fn color() {
+        assert_eq!(
+            "(#70c710)".parse::<Color>().unwrap(),
+            Color([0x70, 0xc7, 0x10])
+        );
+        assert!("#70c710".parse::<Color>().is_err());
+        assert!("(#70c7)".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn pixels() {
+        let plan = DigPlan::parse(SAMPLE, part1_dir_length).unwrap();
+        let (width, height, pixels) = Drawing::new(&plan, SAMPLE, 1).unwrap().pixels().unwrap();
+
+        assert_eq!((width, height), (7, 10));
+        assert_eq!(pixels[1], Color([0x70, 0xc7, 0x10]));
+        assert_eq!(pixels[0], Color([0x7a, 0x21, 0xe3]));
+        assert_eq!(pixels[width + 1], INTERIOR);
+        assert_eq!(pixels[3 * width], BACKGROUND);
+        assert_eq!(pixels.iter().filter(|&&p| p != BACKGROUND).count(), 62);
+    }
+
+    #[test]
+    fn write_svg_and_png() {
+        let plan = DigPlan::parse(SAMPLE, part2_dir_length).unwrap();
+        let drawing = Drawing::new(&plan, SAMPLE, 10_000).unwrap();
+
+        let mut svg = vec![];
+        drawing.write_svg(&mut svg).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+
+        assert!(svg.contains(r##"stroke="#70c710""##));
+        assert_eq!(svg.matches("<line").count(), 14);
+
+        let mut png = vec![];
+        drawing.write_png(&mut png).unwrap();
+
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+
+        assert!(Drawing::new(&plan, SAMPLE, 0).is_err());
+    }
+
+    #[test]
+    fn too_big() {
+        let plan = DigPlan::parse(SAMPLE, part2_dir_length).unwrap();
+
+        // about 1.2 million blocks each way
+        let drawing = Drawing::new(&plan, SAMPLE, 1).unwrap();
+        assert!(drawing.pixels().is_err());
+        assert!(drawing.write_png(vec![]).is_err());
+        assert!(drawing.write_svg(vec![]).is_ok());
+
+        assert!(Drawing::new(&plan, SAMPLE, 1000).unwrap().pixels().is_ok());
+    }
+}
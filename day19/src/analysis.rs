@@ -0,0 +1,337 @@
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{Categories, Next, RangePart, Workflow};
+
+/// something wrong with a set of workflows
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Issue<'a> {
+    /// a rule, or the entry point if there's no rule, sends parts to a
+    /// workflow that doesn't exist
+    Undefined {
+        name: &'a str,
+        from: Option<(&'a str, usize)>,
+    },
+    /// no part can ever get to the workflow from the entry point
+    Unreachable { workflow: &'a str },
+    /// the rules before this one already catch every part it could match
+    DeadRule { workflow: &'a str, rule: usize },
+    /// workflows that some parts go round forever
+    Cycle { workflows: Vec<&'a str> },
+}
+
+impl Issue<'_> {
+    /// whether classifying parts with these workflows could panic or never
+    /// finish
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, Issue::Undefined { .. } | Issue::Cycle { .. })
+    }
+}
+
+/// every issue with the workflows, starting from the workflow called `entry`
+pub fn analyze<'a>(
+    workflows: &HashMap<&'a str, Workflow<'a>>,
+    categories: &Categories,
+    entry: &'a str,
+) -> Vec<Issue<'a>> {
+    let mut names = workflows.keys().copied().collect::<Vec<_>>();
+    names.sort_unstable();
+
+    let mut issues = vec![];
+
+    if !workflows.contains_key(entry) {
+        issues.push(Issue::Undefined {
+            name: entry,
+            from: None,
+        });
+    }
+
+    // the workflows each workflow can send parts to
+    let mut edges = HashMap::new();
+
+    for &name in &names {
+        let mut to = vec![];
+        let mut parts = vec![RangePart::full(categories)];
+
+        for (rule, op) in workflows[name].ops.iter().enumerate() {
+            if let Next::Workflow(n) = op.next() {
+                if workflows.contains_key(n) {
+                    to.push(n);
+                } else {
+                    issues.push(Issue::Undefined {
+                        name: n,
+                        from: Some((name, rule)),
+                    });
+                }
+            }
+
+            let mut matched = false;
+            let mut rest = vec![];
+
+            for part in parts {
+                let (matching, left) = part.split(op);
+                matched |= !matching.is_empty();
+                rest.extend(left);
+            }
+
+            if !matched {
+                issues.push(Issue::DeadRule {
+                    workflow: name,
+                    rule,
+                });
+            }
+
+            parts = rest;
+        }
+
+        edges.insert(name, to);
+    }
+
+    let mut reached = vec![entry];
+    let mut stack = vec![entry];
+
+    while let Some(name) = stack.pop() {
+        for &next in edges.get(name).into_iter().flatten() {
+            if !reached.contains(&next) {
+                reached.push(next);
+                stack.push(next);
+            }
+        }
+    }
+
+    let unreachable = names
+        .iter()
+        .copied()
+        .filter(|name| !reached.contains(name))
+        .collect::<Vec<_>>();
+
+    issues.extend(
+        unreachable
+            .iter()
+            .map(|&workflow| Issue::Unreachable { workflow }),
+    );
+
+    // a loop that nothing gets to yet is still a loop as soon as something
+    // does, and it'd trip up anything that walks the workflows
+    let starts = [entry].into_iter().chain(unreachable).collect::<Vec<_>>();
+
+    issues.extend(
+        cycles(workflows, categories, &starts)
+            .into_iter()
+            .map(|workflows| Issue::Cycle { workflows }),
+    );
+
+    issues
+}
+
+/// the loops that parts really go round, found by following every range of
+/// parts from each of the `starts`, since parts that come back to a workflow
+/// they have already been through will go round the same way forever
+///
+/// each loop is the workflows in it, sorted
+fn cycles<'a>(
+    workflows: &HashMap<&'a str, Workflow<'a>>,
+    categories: &Categories,
+    starts: &[&'a str],
+) -> Vec<Vec<&'a str>> {
+    let mut cycles = vec![];
+
+    // the ranges still to follow, along with every workflow they went through
+    let mut stack = starts
+        .iter()
+        .filter(|start| workflows.contains_key(*start))
+        .map(|&start| (vec![start], RangePart::full(categories)))
+        .collect::<Vec<_>>();
+
+    while let Some((path, part)) = stack.pop() {
+        let mut parts = vec![part];
+
+        for op in &workflows[path[path.len() - 1]].ops {
+            let mut rest = vec![];
+
+            for part in parts {
+                let (matching, left) = part.split(op);
+                rest.extend(left);
+
+                let Next::Workflow(next) = op.next() else {
+                    continue;
+                };
+
+                if matching.is_empty() || !workflows.contains_key(next) {
+                    continue;
+                }
+
+                match path.iter().position(|&n| n == next) {
+                    Some(at) => {
+                        let mut cycle = path[at..].to_vec();
+                        cycle.sort_unstable();
+
+                        if !cycles.contains(&cycle) {
+                            cycles.push(cycle);
+                        }
+                    }
+                    None => stack.extend(
+                        matching
+                            .into_iter()
+                            .map(|m| ([&path[..], &[next]].concat(), m)),
+                    ),
+                }
+            }
+
+            parts = rest;
+        }
+    }
+
+    cycles.sort_unstable();
+    cycles
+}
+
+impl Display for Issue<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Issue::Undefined {
+                name,
+                from: Some((workflow, rule)),
+            } => write!(
+                f,
+                "rule {rule} of {workflow} goes to {name}, which doesn't exist"
+            ),
+            Issue::Undefined { name, from: None } => {
+                write!(f, "there's no {name} workflow to start from")
+            }
+            Issue::Unreachable { workflow } => write!(f, "nothing ever goes to {workflow}"),
+            Issue::DeadRule { workflow, rule } => {
+                write!(f, "rule {rule} of {workflow} can never match")
+            }
+            Issue::Cycle { workflows } => {
+                write!(f, "{} can go round in a loop", workflows.join(", "))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{analyze, Issue};
+    use crate::{count_combinations, parse, tests::SAMPLE};
+
+    #[test]
+    fn sample() {
+        let (workflows, _, categories) = parse(SAMPLE).unwrap();
+
+        assert_eq!(analyze(&workflows, &categories, "in"), vec![]);
+    }
+
+    #[test]
+    fn issues() {
+        let (workflows, _, categories) = parse(
+            "in{x<10:a,x<5:R,m>4000:R,b}
+a{s>10:b,A}
+b{a<100:a,nope}
+c{A}
+
+",
+        )
+        .unwrap();
+
+        let issues = analyze(&workflows, &categories, "in");
+
+        assert_eq!(
+            issues,
+            vec![
+                Issue::Undefined {
+                    name: "nope",
+                    from: Some(("b", 1))
+                },
+                Issue::DeadRule {
+                    workflow: "in",
+                    rule: 1
+                },
+                Issue::DeadRule {
+                    workflow: "in",
+                    rule: 2
+                },
+                Issue::Unreachable { workflow: "c" },
+                Issue::Cycle {
+                    workflows: vec!["a", "b"]
+                },
+            ]
+        );
+        assert!(issues.iter().any(Issue::is_fatal));
+
+        let (workflows, _, categories) = parse("in{x<10:A,x>5:R,A}\nb{b}\n\n").unwrap();
+
+        assert_eq!(
+            analyze(&workflows, &categories, "in"),
+            vec![
+                Issue::DeadRule {
+                    workflow: "in",
+                    rule: 2
+                },
+                Issue::Unreachable { workflow: "b" },
+                Issue::Cycle {
+                    workflows: vec!["b"]
+                },
+            ]
+        );
+        assert_eq!(
+            analyze(&workflows, &categories, "start"),
+            vec![
+                Issue::Undefined {
+                    name: "start",
+                    from: None
+                },
+                Issue::DeadRule {
+                    workflow: "in",
+                    rule: 2
+                },
+                Issue::Unreachable { workflow: "b" },
+                Issue::Unreachable { workflow: "in" },
+                Issue::Cycle {
+                    workflows: vec!["b"]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn cycles() {
+        // the way back to in is only for parts that never get to b, and x is
+        // the only category
+        let (workflows, _, categories) = parse("in{x>10:b,A}\nb{x<5:in,R}\n\n").unwrap();
+
+        assert_eq!(analyze(&workflows, &categories, "in"), vec![]);
+        assert_eq!(count_combinations(&workflows, &categories), 10);
+
+        // parts with a small a go round in, a and b, and the rest stop at b
+        let (workflows, _, categories) =
+            parse("in{x>10:a,A}\na{m>5:b,R}\nb{a<3:in,s>2:b,A}\n\n").unwrap();
+
+        assert_eq!(
+            analyze(&workflows, &categories, "in"),
+            vec![
+                Issue::Cycle {
+                    workflows: vec!["a", "b", "in"]
+                },
+                Issue::Cycle {
+                    workflows: vec!["b"]
+                },
+            ]
+        );
+
+        // nothing gets to u or v, but they still loop for parts with a small x
+        let (workflows, _, categories) = parse("in{A}\nu{x<5:v,A}\nv{u}\n\n").unwrap();
+        let issues = analyze(&workflows, &categories, "in");
+
+        assert_eq!(
+            issues,
+            vec![
+                Issue::Unreachable { workflow: "u" },
+                Issue::Unreachable { workflow: "v" },
+                Issue::Cycle {
+                    workflows: vec!["u", "v"]
+                },
+            ]
+        );
+        assert!(issues.iter().any(Issue::is_fatal));
+    }
+}
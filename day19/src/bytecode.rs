@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::{Compare, Next, Op, Part, Workflow};
+
+/// where an instruction sends a part: to another instruction, or out of the
+/// program altogether
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Instr(usize),
+    Accept,
+    Reject,
+}
+
+/// a single comparison with the workflow names already looked up, where a
+/// part that doesn't pass falls through to the next instruction
+///
+/// a rule with several comparisons becomes one instruction for each, where
+/// all but the last skip past the rest if the part fails them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instr<'a> {
+    If(Compare<'a>, Target),
+    Jump(Target),
+}
+
+/// every workflow laid out one after another in a single list of
+/// instructions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program<'a> {
+    instrs: Vec<Instr<'a>>,
+    entry: Target,
+}
+
+impl<'a> Program<'a> {
+    /// lays out the workflows and resolves every jump between them, starting
+    /// at the workflow called `entry`
+    pub fn compile(workflows: &HashMap<&str, Workflow<'a>>, entry: &str) -> Result<Self> {
+        // sorted so that the same workflows always compile the same way
+        let mut names = workflows.keys().copied().collect::<Vec<_>>();
+        names.sort_unstable();
+
+        let mut starts = HashMap::new();
+        let mut len = 0;
+
+        for name in &names {
+            starts.insert(*name, len);
+            len += workflows[name]
+                .ops
+                .iter()
+                .map(|op| op.compares().len().max(1))
+                .sum::<usize>();
+        }
+
+        let resolve = |next: &Next| match next {
+            Next::Accepted => Ok(Target::Accept),
+            Next::Rejected => Ok(Target::Reject),
+            Next::Workflow(n) => starts
+                .get(n)
+                .map(|&start| Target::Instr(start))
+                .ok_or(anyhow!("there's no workflow called {n}")),
+        };
+
+        // a part that no rule matches would fall through into whatever
+        // workflow is laid out next
+        if let Some(name) = names
+            .iter()
+            .find(|name| !matches!(workflows[*name].ops.last(), Some(Op::Final(_))))
+        {
+            bail!("{name} doesn't end with a rule for every part");
+        }
+
+        let mut instrs = vec![];
+
+        for op in names.iter().flat_map(|name| &workflows[name].ops) {
+            match op {
+                Op::If(compares, next) => {
+                    let (last, rest) = compares.split_last().unwrap();
+                    let skip = Target::Instr(instrs.len() + compares.len());
+
+                    instrs.extend(rest.iter().map(|c| Instr::If(c.negate(), skip)));
+                    instrs.push(Instr::If(*last, resolve(next)?));
+                }
+                Op::Final(next) => instrs.push(Instr::Jump(resolve(next)?)),
+            }
+        }
+
+        let entry = resolve(&Next::Workflow(entry))?;
+
+        Ok(Self { instrs, entry })
+    }
+
+    pub fn instrs(&self) -> &[Instr<'a>] {
+        &self.instrs
+    }
+
+    pub fn accepts(&self, part: &Part) -> bool {
+        let mut target = self.entry;
+
+        loop {
+            let pc = match target {
+                Target::Accept => return true,
+                Target::Reject => return false,
+                Target::Instr(pc) => pc,
+            };
+
+            target = match self.instrs[pc] {
+                Instr::If(c, t) if c.holds(part) => t,
+                Instr::If(..) => Target::Instr(pc + 1),
+                Instr::Jump(t) => t,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::rng::XorShift;
+
+    use super::{Instr, Program, Target};
+    use crate::{interpret, parse, tests::SAMPLE, Compare, Part};
+
+    fn compare(s: &str) -> Compare<'_> {
+        Compare::try_from(s).unwrap()
+    }
+
+    #[test]
+    fn compile() {
+        let (workflows, ..) = parse("in{x<10:A,b}\nb{m>5:R,A}\n\n").unwrap();
+        let program = Program::compile(&workflows, "in").unwrap();
+
+        assert_eq!(
+            program.instrs(),
+            &[
+                Instr::If(compare("m>5"), Target::Reject),
+                Instr::Jump(Target::Accept),
+                Instr::If(compare("x<10"), Target::Accept),
+                Instr::Jump(Target::Instr(0)),
+            ]
+        );
+
+        let (workflows, ..) = parse("in{x>10&m<5&a==3:A,b}\nb{m!=5:R,A}\n\n").unwrap();
+        let program = Program::compile(&workflows, "in").unwrap();
+
+        assert_eq!(
+            program.instrs(),
+            &[
+                Instr::If(compare("m!=5"), Target::Reject),
+                Instr::Jump(Target::Accept),
+                Instr::If(compare("x<=10"), Target::Instr(5)),
+                Instr::If(compare("m>=5"), Target::Instr(5)),
+                Instr::If(compare("a==3"), Target::Accept),
+                Instr::Jump(Target::Instr(0)),
+            ]
+        );
+
+        let (workflows, ..) = parse("in{x<10:A,c}\nb{m>5:R,A}\n\n").unwrap();
+        assert!(Program::compile(&workflows, "in").is_err());
+    }
+
+    /// a workflow that ends with a comparison would let parts fall through
+    /// into the next one
+    #[test]
+    fn missing_fallthrough() {
+        assert!(parse("in{x<10:A}\nzz{R}\n\n{x=20}").is_err());
+
+        let (mut workflows, ..) = parse("in{x<10:A,R}\nzz{R}\n\n{x=20}").unwrap();
+        workflows.get_mut("in").unwrap().ops.pop();
+
+        assert!(Program::compile(&workflows, "in").is_err());
+    }
+
+    /// classifies random parts with both, using a small xorshift generator
+    #[test]
+    fn matches_interpreter() {
+        let (workflows, parts, categories) = parse(SAMPLE).unwrap();
+        let program = Program::compile(&workflows, "in").unwrap();
+
+        for part in &parts {
+            assert_eq!(program.accepts(part), interpret(&workflows, part));
+        }
+
+        let accepted = parts
+            .iter()
+            .filter(|part| program.accepts(part))
+            .map(Part::total)
+            .sum::<u64>();
+        assert_eq!(accepted, 19114);
+
+        let mut rng = XorShift::default();
+        let mut next = move || rng.in_range(1..=4000);
+
+        for _ in 0..10_000 {
+            let part = categories
+                .names
+                .iter()
+                .map(|&c| (c, next()))
+                .collect::<Part>();
+
+            assert_eq!(program.accepts(&part), interpret(&workflows, &part));
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    fmt::Display,
+    iter::Sum,
+    ops::{Add, AddAssign, Mul},
+};
+
+/// a number of parts, which can be far more than a u128 holds once there are
+/// a few categories with a lot of ratings in each
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Count {
+    /// the digits in base 2^64, least significant first, with no zeros at
+    /// the end so every number has just the one representation
+    limbs: Vec<u64>,
+}
+
+impl Count {
+    /// the product of some numbers, like the widths of a box of ratings
+    pub fn product(factors: impl IntoIterator<Item = u64>) -> Self {
+        factors
+            .into_iter()
+            .fold(Count::from(1u128), |count, factor| count * factor)
+    }
+
+    fn trim(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+
+        self
+    }
+
+    /// divides by `divisor` in place, returning the remainder
+    fn div_rem(&mut self, divisor: u64) -> u64 {
+        let mut rem = 0u128;
+
+        for limb in self.limbs.iter_mut().rev() {
+            let n = (rem << 64) | *limb as u128;
+            *limb = (n / divisor as u128) as u64;
+            rem = n % divisor as u128;
+        }
+
+        *self = std::mem::take(self).trim();
+
+        rem as u64
+    }
+}
+
+impl From<u128> for Count {
+    fn from(n: u128) -> Self {
+        Count {
+            limbs: vec![n as u64, (n >> 64) as u64],
+        }
+        .trim()
+    }
+}
+
+impl PartialEq<u128> for Count {
+    fn eq(&self, other: &u128) -> bool {
+        let limb = |i: usize| self.limbs.get(i).copied().unwrap_or(0);
+
+        self.limbs.len() <= 2 && limb(0) == *other as u64 && limb(1) == (*other >> 64) as u64
+    }
+}
+
+impl Mul<u64> for Count {
+    type Output = Count;
+
+    fn mul(mut self, factor: u64) -> Count {
+        let mut carry = 0u128;
+
+        for limb in &mut self.limbs {
+            let n = *limb as u128 * factor as u128 + carry;
+            *limb = n as u64;
+            carry = n >> 64;
+        }
+
+        self.limbs.push(carry as u64);
+        self.trim()
+    }
+}
+
+impl AddAssign<&Count> for Count {
+    fn add_assign(&mut self, other: &Count) {
+        if self.limbs.len() < other.limbs.len() {
+            self.limbs.resize(other.limbs.len(), 0);
+        }
+
+        let mut carry = false;
+
+        for (i, limb) in self.limbs.iter_mut().enumerate() {
+            let (n, c1) = limb.overflowing_add(other.limbs.get(i).copied().unwrap_or(0));
+            let (n, c2) = n.overflowing_add(carry as u64);
+
+            *limb = n;
+            carry = c1 || c2;
+        }
+
+        if carry {
+            self.limbs.push(1);
+        }
+    }
+}
+
+impl Add for Count {
+    type Output = Count;
+
+    fn add(mut self, other: Count) -> Count {
+        self += &other;
+        self
+    }
+}
+
+impl Sum for Count {
+    fn sum<I: Iterator<Item = Count>>(iter: I) -> Self {
+        iter.fold(Count::default(), Add::add)
+    }
+}
+
+impl Display for Count {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // the biggest power of ten that fits in a u64
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+
+        let mut n = self.clone();
+        let mut chunks = vec![];
+
+        while !n.limbs.is_empty() {
+            chunks.push(n.div_rem(CHUNK));
+        }
+
+        let mut chunks = chunks.into_iter().rev();
+        write!(f, "{}", chunks.next().unwrap_or(0))?;
+
+        for chunk in chunks {
+            write!(f, "{chunk:019}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Count;
+
+    #[test]
+    fn count() {
+        assert_eq!(Count::default().to_string(), "0");
+        assert_eq!(Count::from(0u128), Count::default());
+        assert_eq!(Count::from(u128::MAX).to_string(), u128::MAX.to_string());
+
+        let big = Count::product([u64::MAX; 3]);
+        assert_eq!(
+            big.to_string(),
+            "6277101735386680762814942322444851025767571854389858533375"
+        );
+
+        assert_eq!(
+            Count::product([1_000_000_000; 6]).to_string(),
+            format!("1{}", "0".repeat(54))
+        );
+        assert_eq!(Count::product([u64::MAX, 0]), 0);
+
+        let sum = [
+            Count::from(u128::MAX),
+            Count::from(1u128),
+            Count::from(u128::MAX),
+        ]
+        .into_iter()
+        .sum::<Count>();
+        assert_eq!(sum.to_string(), "680564733841876926926749214863536422911");
+    }
+}
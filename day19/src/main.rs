@@ -1,284 +1,426 @@
-use std::{collections::HashMap, ops::Range, str::FromStr};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    ops::{Range, RangeInclusive},
+};
 
 use anyhow::{anyhow, bail, Error, Result};
-use shared::dprintln;
+use shared::{dprintln, rng::XorShift};
+
+use analysis::analyze;
+use bytecode::Program;
+use count::Count;
+
+mod analysis;
+mod bytecode;
+mod count;
+mod simplify;
 
 const INPUT: &str = include_str!("../input");
 
+/// the ratings every category can have unless told otherwise
+const RATINGS: RangeInclusive<u64> = 1..=4000;
+
 fn main() -> Result<()> {
-    let (workflows, parts) = INPUT.split_once("\n\n").ok_or(anyhow!("couldn't split"))?;
+    let (workflows, parts, mut categories) = parse(INPUT)?;
+
+    // `day19 check` lists everything wrong with the workflows,
+    // `day19 synthetic <n>` classifies n random parts with the compiled
+    // workflows, `day19 simplify` lists the simplified workflows and every
+    // box of accepted parts, and `day19 dot` writes the simplified workflows
+    // as a graphviz graph, instead of solving, while
+    // `day19 bounds <min> <max>` solves with ratings from min to max
+    let mut args = std::env::args().skip(1);
+    let mode = args.next();
+
+    if mode.as_deref() == Some("bounds") {
+        let mut bound = || -> Result<u64> {
+            Ok(args
+                .next()
+                .ok_or(anyhow!("expected a minimum and maximum rating"))?
+                .parse()?)
+        };
+
+        categories = categories.with_bounds(bound()?..=bound()?)?;
+    }
+
+    let issues = analyze(&workflows, &categories, "in");
+
+    if mode.as_deref() == Some("check") {
+        for issue in &issues {
+            println!("{issue}");
+        }
+
+        return Ok(());
+    }
+
+    // these would make solving panic or never finish
+    if let Some(issue) = issues.iter().find(|i| i.is_fatal()) {
+        bail!("{issue}");
+    }
+
+    dprintln!("issues: {issues:?}");
+
+    if mode.as_deref() == Some("simplify") {
+        let simplified = simplify::simplify(&workflows, "in");
+        print!("{}", simplify::listing(&simplified, "in"));
+
+        println!("accepted:");
+        for region in accepted_regions(&simplified, &categories) {
+            println!("    {region}");
+        }
+
+        return Ok(());
+    }
+
+    if mode.as_deref() == Some("dot") {
+        let simplified = simplify::simplify(&workflows, "in");
+        simplify::write_dot(&simplified, "in", std::io::stdout().lock())?;
+
+        return Ok(());
+    }
+
+    let program = Program::compile(&workflows, "in")?;
+
+    if mode.as_deref() == Some("synthetic") {
+        let n = args
+            .next()
+            .ok_or(anyhow!("expected a number of parts"))?
+            .parse::<usize>()?;
+
+        let mut rng = XorShift::default();
+        let mut next = move || rng.in_range(categories.bounds.clone());
+
+        let accepted = (0..n)
+            .filter(|_| {
+                let part = categories.names.iter().map(|&c| (c, next())).collect();
+                program.accepts(&part)
+            })
+            .count();
+
+        println!(
+            "accepted {accepted} of {n} with {} instructions",
+            program.instrs().len()
+        );
+
+        return Ok(());
+    }
+
+    let mut sum = 0;
+
+    for part in parts {
+        dprintln!("part: {part:?}\n  in");
+        let accepted = program.accepts(&part);
+        debug_assert_eq!(accepted, interpret(&workflows, &part));
+
+        if accepted {
+            sum += part.total();
+        }
+    }
+
+    println!("part1: {sum}");
+
+    println!("part2: {}", count_combinations(&workflows, &categories));
+
+    Ok(())
+}
+
+/// the workflows, the parts, and every category either of them rates parts
+/// in, with ratings from 1 to 4000
+fn parse(input: &str) -> Result<(HashMap<&str, Workflow<'_>>, Vec<Part<'_>>, Categories<'_>)> {
+    let (workflows, parts) = input.split_once("\n\n").ok_or(anyhow!("couldn't split"))?;
 
     let workflows = workflows
         .lines()
         .map(TryFrom::try_from)
-        .map(|e| e.map(|w: Workflow| (w.name, w)))
-        .collect::<Result<HashMap<&str, Workflow>, _>>()?;
+        .collect::<Result<Vec<Workflow>, _>>()?;
 
     let parts = parts
         .lines()
-        .map(FromStr::from_str)
+        .map(TryFrom::try_from)
         .collect::<Result<Vec<Part>, _>>()?;
 
-    let mut sum = 0;
+    // in the order the parts list them, then any only the rules mention
+    let mut names = vec![];
+    let rated = parts.iter().flat_map(|p| p.ratings.iter().map(|&(c, _)| c));
+    let compared = workflows
+        .iter()
+        .flat_map(|w| &w.ops)
+        .flat_map(Op::compares)
+        .map(|c| c.category);
+
+    for category in rated.chain(compared) {
+        if !names.contains(&category) {
+            names.push(category);
+        }
+    }
 
-    for part in parts {
-        dprintln!("part: {part:?}\n  in");
-        let mut current_workflow = workflows.get("in").unwrap();
-        let mut current_index = 0;
-        let accepted = loop {
-            let next = match &current_workflow.ops[current_index] {
-                Op::Gt(c, num, next) => (part.get_category(*c) > *num).then_some(next),
-                Op::Lt(c, num, next) => (part.get_category(*c) < *num).then_some(next),
-                Op::Final(next) => Some(next),
-            };
+    for part in &parts {
+        if let Some(c) = names.iter().find(|&&c| !part.has_category(c)) {
+            bail!("{part:?} has no {c} rating");
+        }
+    }
 
-            match next {
-                Some(Next::Rejected) => {
-                    dprintln!("  R");
-                    break false;
-                }
-                Some(Next::Accepted) => {
-                    dprintln!("  A");
-                    break true;
-                }
-                Some(Next::Workflow(n)) => {
-                    dprintln!("  {n}");
-                    current_workflow = workflows.get(n).unwrap();
-                    current_index = 0;
-                }
-                None => {
-                    current_index += 1;
-                }
-            }
+    let workflows = workflows.into_iter().map(|w| (w.name, w)).collect();
+
+    Ok((workflows, parts, Categories::new(names, RATINGS)?))
+}
+
+/// runs a part through the workflows by name, one rule at a time
+fn interpret(workflows: &HashMap<&str, Workflow>, part: &Part) -> bool {
+    let mut current_workflow = workflows.get("in").unwrap();
+    let mut current_index = 0;
+
+    loop {
+        let next = match &current_workflow.ops[current_index] {
+            Op::If(compares, next) => compares.iter().all(|c| c.holds(part)).then_some(next),
+            Op::Final(next) => Some(next),
         };
 
-        if accepted {
-            sum += part.x + part.m + part.a + part.s;
+        match next {
+            Some(Next::Rejected) => {
+                dprintln!("  R");
+                break false;
+            }
+            Some(Next::Accepted) => {
+                dprintln!("  A");
+                break true;
+            }
+            Some(Next::Workflow(n)) => {
+                dprintln!("  {n}");
+                current_workflow = workflows.get(n).unwrap();
+                current_index = 0;
+            }
+            None => {
+                current_index += 1;
+            }
         }
     }
+}
 
-    println!("part1: {sum}");
+/// the number of parts with ratings in the categories' bounds that end up
+/// accepted
+fn count_combinations(workflows: &HashMap<&str, Workflow>, categories: &Categories) -> Count {
+    accepted_regions(workflows, categories)
+        .iter()
+        .map(RangePart::num_combinations)
+        .sum()
+}
 
-    let mut stack = vec![(
-        RangePart {
-            x: 1..4001,
-            m: 1..4001,
-            a: 1..4001,
-            s: 1..4001,
-        },
-        Next::Workflow("in"),
-        0,
-    )];
+/// every part that ends up accepted, as boxes of ratings that don't overlap
+fn accepted_regions<'a>(
+    workflows: &HashMap<&str, Workflow>,
+    categories: &Categories<'a>,
+) -> Vec<RangePart<'a>> {
+    let mut stack = vec![(RangePart::full(categories), Next::Workflow("in"), 0)];
 
-    let mut sum = 0;
+    let mut regions = vec![];
 
-    'outer: while let Some((mut part, workflow, mut current_index)) = stack.pop() {
+    while let Some((part, next, rule)) = stack.pop() {
         dprintln!("{} {part:?}", stack.len());
-        let workflow = match workflow {
+        let workflow = match next {
             Next::Workflow(w) => w,
             Next::Accepted => {
-                sum += part.num_combinations();
+                regions.push(part);
                 continue;
             }
             Next::Rejected => continue,
         };
 
-        let mut current_workflow = workflows.get(workflow).unwrap();
-
-        loop {
-            dprintln!(
-                "  {:?} {} {:?}",
-                part,
-                current_workflow.name,
-                current_workflow.ops[current_index]
-            );
-            let next = match &current_workflow.ops[current_index] {
-                Op::Gt(c, num, next) => match part.split_at_gt(*c, *num) {
-                    Ok((o, p)) => {
-                        stack.push((o, *next, 0));
-                        part = p;
-                        None
-                    }
-                    Err(p) => {
-                        part = p;
-                        None
-                    }
-                },
-                Op::Lt(c, num, next) => match part.split_at_lt(*c, *num) {
-                    Ok((o, p)) => {
-                        stack.push((o, *next, 0));
-                        part = p;
-                        None
-                    }
-                    Err(p) => {
-                        part = p;
-                        None
-                    }
-                },
-                Op::Final(next) => Some(next),
-            };
+        let Some(op) = workflows.get(workflow).unwrap().ops.get(rule) else {
+            continue;
+        };
 
-            match next {
-                Some(Next::Rejected) => {
-                    continue 'outer;
-                }
-                Some(a @ Next::Accepted) => {
-                    stack.push((part, *a, 0));
-                    continue 'outer;
-                }
-                Some(Next::Workflow(n)) => {
-                    dprintln!("  {n}");
-                    current_workflow = workflows.get(n).unwrap();
-                    current_index = 0;
-                }
-                None => {
-                    current_index += 1;
-                }
-            }
-        }
+        dprintln!("  {part:?} {workflow} {op:?}");
+        let (matching, rest) = part.split(op);
+
+        stack.extend(matching.into_iter().map(|p| (p, op.next(), 0)));
+        stack.extend(rest.into_iter().map(|p| (p, next, rule + 1)));
     }
 
-    println!("part2: {sum}");
+    regions
+}
 
-    Ok(())
+/// the categories parts are rated in, in the order they're first seen, and
+/// the ratings a part can have in each of them
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Categories<'a> {
+    names: Vec<&'a str>,
+    bounds: RangeInclusive<u64>,
+}
+
+impl<'a> Categories<'a> {
+    /// with any number of categories, since parts are counted with a
+    /// [`Count`] that never overflows
+    fn new(names: Vec<&'a str>, bounds: RangeInclusive<u64>) -> Result<Self> {
+        let (min, max) = (*bounds.start(), *bounds.end());
+
+        if min > max || max == u64::MAX {
+            bail!("ratings can't go from {min} to {max}");
+        }
+
+        Ok(Self { names, bounds })
+    }
+
+    fn with_bounds(self, bounds: RangeInclusive<u64>) -> Result<Self> {
+        Self::new(self.names, bounds)
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum Category {
-    X,
-    M,
-    A,
-    S,
+/// a part's rating in each category
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Part<'a> {
+    ratings: Vec<(&'a str, u64)>,
 }
 
-impl FromStr for Category {
-    type Err = Error;
+impl Part<'_> {
+    fn get_category(&self, category: &str) -> u64 {
+        self.ratings
+            .iter()
+            .find(|&&(c, _)| c == category)
+            .map(|&(_, rating)| rating)
+            .unwrap_or_else(|| panic!("{self:?} has no {category} rating"))
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "x" => Self::X,
-            "m" => Self::M,
-            "a" => Self::A,
-            "s" => Self::S,
-            _ => bail!("couldn't parse the category"),
-        })
+    fn has_category(&self, category: &str) -> bool {
+        self.ratings.iter().any(|&(c, _)| c == category)
+    }
+
+    fn total(&self) -> u64 {
+        self.ratings.iter().map(|&(_, rating)| rating).sum()
     }
 }
 
-#[derive(Debug)]
-struct Part {
-    x: u64,
-    m: u64,
-    a: u64,
-    s: u64,
+impl<'a> FromIterator<(&'a str, u64)> for Part<'a> {
+    fn from_iter<T: IntoIterator<Item = (&'a str, u64)>>(iter: T) -> Self {
+        Self {
+            ratings: iter.into_iter().collect(),
+        }
+    }
 }
 
-impl Part {
-    fn get_category(&self, category: Category) -> u64 {
-        match category {
-            Category::X => self.x,
-            Category::M => self.m,
-            Category::A => self.a,
-            Category::S => self.s,
+impl<'a> TryFrom<&'a str> for Part<'a> {
+    type Error = Error;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        let mut ratings: Vec<(&str, u64)> = vec![];
+
+        let inside = s
+            .strip_prefix('{')
+            .and_then(|s| s.strip_suffix('}'))
+            .ok_or(anyhow!("{s} isn't in braces"))?;
+
+        for rating in inside.split(',') {
+            let (c, r) = rating
+                .split_once('=')
+                .ok_or(anyhow!("couldn't find the rating in {rating}"))?;
+
+            if ratings.iter().any(|&(d, _)| d == c) {
+                bail!("{s} rates {c} more than once");
+            }
+
+            ratings.push((category(c)?, r.parse()?));
         }
+
+        Ok(Self { ratings })
     }
 }
 
+/// a category name, which is made of lowercase letters
+fn category(s: &str) -> Result<&str> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_lowercase()) {
+        bail!("couldn't parse the category {s}");
+    }
+
+    Ok(s)
+}
+
+/// every part with ratings in some range in each category
 #[derive(Debug, Clone)]
-struct RangePart {
-    x: Range<u64>,
-    m: Range<u64>,
-    a: Range<u64>,
-    s: Range<u64>,
+struct RangePart<'a> {
+    ranges: Vec<(&'a str, Range<u64>)>,
 }
 
-impl RangePart {
-    fn split_at_gt(self, category: Category, v: u64) -> Result<(RangePart, RangePart), RangePart> {
-        match category {
-            Category::X => match self.x.split_gt(v) {
-                None => Err(self),
-                Some((x, o)) => Ok((RangePart { x, ..self.clone() }, RangePart { x: o, ..self })),
-            },
-            Category::M => match self.m.split_gt(v) {
-                None => Err(self),
-                Some((m, o)) => Ok((RangePart { m, ..self.clone() }, RangePart { m: o, ..self })),
-            },
-            Category::A => match self.a.split_gt(v) {
-                None => Err(self),
-                Some((a, o)) => Ok((RangePart { a, ..self.clone() }, RangePart { a: o, ..self })),
-            },
-            Category::S => match self.s.split_gt(v) {
-                None => Err(self),
-                Some((s, o)) => Ok((RangePart { s, ..self.clone() }, RangePart { s: o, ..self })),
-            },
-        }
-    }
+impl<'a> RangePart<'a> {
+    /// every part with ratings in the categories' bounds
+    fn full(categories: &Categories<'a>) -> Self {
+        let range = *categories.bounds.start()..*categories.bounds.end() + 1;
 
-    fn split_at_lt(self, category: Category, v: u64) -> Result<(RangePart, RangePart), RangePart> {
-        match category {
-            Category::X => match self.x.split_lt(v) {
-                None => Err(self),
-                Some((x, o)) => Ok((RangePart { x, ..self.clone() }, RangePart { x: o, ..self })),
-            },
-            Category::M => match self.m.split_lt(v) {
-                None => Err(self),
-                Some((m, o)) => Ok((RangePart { m, ..self.clone() }, RangePart { m: o, ..self })),
-            },
-            Category::A => match self.a.split_lt(v) {
-                None => Err(self),
-                Some((a, o)) => Ok((RangePart { a, ..self.clone() }, RangePart { a: o, ..self })),
-            },
-            Category::S => match self.s.split_lt(v) {
-                None => Err(self),
-                Some((s, o)) => Ok((RangePart { s, ..self.clone() }, RangePart { s: o, ..self })),
-            },
+        RangePart {
+            ranges: categories
+                .names
+                .iter()
+                .map(|&c| (c, range.clone()))
+                .collect(),
         }
     }
 
-    fn num_combinations(self) -> u64 {
-        let Self {
-            x: x_r,
-            m: m_r,
-            a: a_r,
-            s: s_r,
-        } = self;
+    fn index(&self, category: &str) -> usize {
+        self.ranges
+            .iter()
+            .position(|&(c, _)| c == category)
+            .unwrap_or_else(|| panic!("there's no {category} category"))
+    }
 
-        (x_r.end - x_r.start)
-            * (m_r.end - m_r.start)
-            * (a_r.end - a_r.start)
-            * (s_r.end - s_r.start)
+    /// the parts that an op sends on, and the parts that fall through to the
+    /// next op, as boxes that don't overlap
+    fn split(self, op: &Op) -> (Vec<Self>, Vec<Self>) {
+        let mut matching = vec![self];
+        let mut rest = vec![];
 
-        // x_r.flat_map(|x| m_r.clone().map(move |m| x + m))
-        //     .flat_map(|xm| a_r.clone().map(move |a| xm + a))
-        //     .flat_map(|xma| s_r.clone().map(move |s| xma + s))
-        //     .sum()
-    }
-}
+        // whatever fails a comparison falls through, and whatever passes
+        // goes on to the next one
+        for compare in op.compares() {
+            let mut passed = vec![];
 
-impl FromStr for Part {
-    type Err = Error;
+            for part in matching {
+                rest.extend(part.restrict(&compare.negate()));
+                passed.extend(part.restrict(compare));
+            }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = &s[3..];
+            matching = passed;
+        }
 
-        let (x, s) = s.split_once(",m=").ok_or(anyhow!("couldn't find m"))?;
+        (matching, rest)
+    }
 
-        let (m, s) = s.split_once(",a=").ok_or(anyhow!("couldn't find a"))?;
+    /// the parts that pass a comparison, in at most two pieces
+    fn restrict(&self, compare: &Compare) -> Vec<Self> {
+        let i = self.index(compare.category);
+        let range = &self.ranges[i].1;
+
+        compare
+            .cmp
+            .passing(compare.value)
+            .into_iter()
+            .map(|r| r.start.max(range.start)..r.end.min(range.end))
+            .filter(|r| !r.is_empty())
+            .map(|r| {
+                let mut part = self.clone();
+                part.ranges[i].1 = r;
+                part
+            })
+            .collect()
+    }
 
-        let (a, s) = s.split_once(",s=").ok_or(anyhow!("couldn't find s"))?;
+    fn num_combinations(&self) -> Count {
+        Count::product(self.ranges.iter().map(|(_, r)| r.end - r.start))
+    }
+}
 
-        let s = &s[..s.len() - 1];
+impl Display for RangePart<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ranges = self
+            .ranges
+            .iter()
+            .map(|(c, r)| format!("{c} {}..={}", r.start, r.end - 1))
+            .collect::<Vec<_>>();
 
-        Ok(Self {
-            x: x.parse()?,
-            m: m.parse()?,
-            a: a.parse()?,
-            s: s.parse()?,
-        })
+        write!(f, "{}", ranges.join(", "))
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Next<'a> {
     Workflow(&'a str),
     Accepted,
@@ -297,29 +439,171 @@ impl<'a> TryFrom<&'a str> for Next<'a> {
     }
 }
 
-#[derive(Debug)]
+/// how a comparison checks a rating against a number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Cmp {
+    Gt,
+    Lt,
+    Ge,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl Cmp {
+    const SYMBOLS: [(&'static str, Cmp); 6] = [
+        (">=", Cmp::Ge),
+        ("<=", Cmp::Le),
+        ("==", Cmp::Eq),
+        ("!=", Cmp::Ne),
+        (">", Cmp::Gt),
+        ("<", Cmp::Lt),
+    ];
+
+    fn holds(self, rating: u64, value: u64) -> bool {
+        match self {
+            Cmp::Gt => rating > value,
+            Cmp::Lt => rating < value,
+            Cmp::Ge => rating >= value,
+            Cmp::Le => rating <= value,
+            Cmp::Eq => rating == value,
+            Cmp::Ne => rating != value,
+        }
+    }
+
+    /// the comparison that holds exactly when this one doesn't
+    fn negate(self) -> Self {
+        match self {
+            Cmp::Gt => Cmp::Le,
+            Cmp::Lt => Cmp::Ge,
+            Cmp::Ge => Cmp::Lt,
+            Cmp::Le => Cmp::Gt,
+            Cmp::Eq => Cmp::Ne,
+            Cmp::Ne => Cmp::Eq,
+        }
+    }
+
+    /// every rating below u64::MAX that the comparison holds for, as two
+    /// ranges since `!=` leaves a gap, where the second is usually empty
+    fn passing(self, value: u64) -> [Range<u64>; 2] {
+        let after = value.saturating_add(1);
+
+        match self {
+            Cmp::Gt => [after..u64::MAX, 0..0],
+            Cmp::Lt => [0..value, 0..0],
+            Cmp::Ge => [value..u64::MAX, 0..0],
+            Cmp::Le => [0..after, 0..0],
+            Cmp::Eq => [value..after, 0..0],
+            Cmp::Ne => [0..value, after..u64::MAX],
+        }
+    }
+}
+
+impl Display for Cmp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (symbol, _) = Cmp::SYMBOLS.iter().find(|(_, c)| c == self).unwrap();
+        write!(f, "{symbol}")
+    }
+}
+
+/// one rating checked against a number, like `x>10`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Compare<'a> {
+    category: &'a str,
+    cmp: Cmp,
+    value: u64,
+}
+
+impl Compare<'_> {
+    fn holds(&self, part: &Part) -> bool {
+        self.cmp.holds(part.get_category(self.category), self.value)
+    }
+
+    fn negate(&self) -> Self {
+        Self {
+            cmp: self.cmp.negate(),
+            ..*self
+        }
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Compare<'a> {
+    type Error = Error;
+
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        let at = s
+            .find(['<', '>', '=', '!'])
+            .ok_or(anyhow!("couldn't find a comparison in {s}"))?;
+        let (c, rest) = s.split_at(at);
+
+        let (cmp, value) = Cmp::SYMBOLS
+            .iter()
+            .find_map(|&(symbol, cmp)| rest.strip_prefix(symbol).map(|v| (cmp, v)))
+            .ok_or(anyhow!("couldn't parse the comparison {s}"))?;
+
+        Ok(Self {
+            category: category(c)?,
+            cmp,
+            value: value.parse()?,
+        })
+    }
+}
+
+impl Display for Compare<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.category, self.cmp, self.value)
+    }
+}
+
+#[derive(Debug, Clone)]
 enum Op<'a> {
-    Gt(Category, u64, Next<'a>),
-    Lt(Category, u64, Next<'a>),
+    /// sends on the parts that pass every comparison
+    If(Vec<Compare<'a>>, Next<'a>),
     Final(Next<'a>),
 }
 
+impl<'a> Op<'a> {
+    fn next(&self) -> Next<'a> {
+        match self {
+            Op::If(_, next) | Op::Final(next) => *next,
+        }
+    }
+
+    /// the comparisons a part has to pass to be sent on, which is none of
+    /// them for a fallthrough
+    fn compares(&self) -> &[Compare<'a>] {
+        match self {
+            Op::If(compares, _) => compares,
+            Op::Final(_) => &[],
+        }
+    }
+
+    fn set_next(&mut self, to: Next<'a>) {
+        match self {
+            Op::If(_, next) | Op::Final(next) => *next = to,
+        }
+    }
+}
+
 impl<'a> TryFrom<&'a str> for Op<'a> {
     type Error = Error;
 
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
         Ok(match value.split_once(':') {
-            Some((s, n)) => match (s.split_once('<'), s.split_once('>')) {
-                (Some((c, w)), _) => Op::Lt(c.parse()?, w.parse()?, n.try_into()?),
-                (_, Some((c, w))) => Op::Gt(c.parse()?, w.parse()?, n.try_into()?),
-                (_, _) => bail!("couldn't parse op"),
-            },
+            Some((s, n)) => {
+                let compares = s
+                    .split('&')
+                    .map(TryFrom::try_from)
+                    .collect::<Result<_, _>>()?;
+
+                Op::If(compares, n.try_into()?)
+            }
             None => Op::Final(value.try_into()?),
         })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct Workflow<'a> {
     ops: Vec<Op<'a>>,
     name: &'a str,
@@ -336,54 +620,208 @@ impl<'a> TryFrom<&'a str> for Workflow<'a> {
         let ops = s.1[..s.1.len() - 1]
             .split(',')
             .map(TryFrom::try_from)
-            .collect::<Result<_, _>>()?;
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // otherwise parts that match no rule would have nowhere to go
+        if !matches!(ops.last(), Some(Op::Final(_))) {
+            bail!("{name} has no last rule for the parts that match nothing else");
+        }
 
         Ok(Self { ops, name })
     }
 }
 
-trait RangeSplit
-where
-    Self: Sized,
-{
-    fn split_gt(&self, val: u64) -> Option<(Self, Self)>;
-    fn split_lt(&self, val: u64) -> Option<(Self, Self)>;
-}
+#[cfg(test)]
+mod tests {
+    use std::ops::RangeInclusive;
+
+    use super::{
+        bytecode, count_combinations, interpret, parse, Cmp, Next, Op, RangePart, RATINGS,
+    };
+
+    pub const SAMPLE: &str = "px{a<2006:qkq,m>2090:A,rfg}
+pv{a>1716:R,A}
+lnx{m>1548:A,A}
+rfg{s<537:gd,x>2440:R,A}
+qs{s>3448:A,lnx}
+qkq{x<1416:A,crn}
+crn{x>2662:A,R}
+in{s<1351:px,qqz}
+qqz{s>2770:qs,m<1801:hdj,R}
+gd{a>3333:R,R}
+hdj{m>838:A,pv}
+
+{x=787,m=2655,a=1222,s=2876}
+{x=1679,m=44,a=2005,s=111}
+{x=2036,m=264,a=79,s=2244}
+{x=2461,m=1339,a=466,s=291}
+{x=2127,m=1623,a=2188,s=1013}";
 
-impl RangeSplit for Range<u64> {
-    fn split_gt(&self, val: u64) -> Option<(Self, Self)> {
-        if self.contains(&val) {
-            Some((val + 1..self.end, self.start..val + 1))
-        } else {
-            None
-        }
+    #[test]
+    fn sample() {
+        let (workflows, parts, categories) = parse(SAMPLE).unwrap();
+
+        assert_eq!(parts.len(), 5);
+        assert_eq!(parts[0].get_category("a"), 1222);
+        assert_eq!(categories.names, vec!["x", "m", "a", "s"]);
+        assert_eq!(categories.bounds, RATINGS);
+        assert_eq!(count_combinations(&workflows, &categories), 167409079868000);
     }
 
-    fn split_lt(&self, val: u64) -> Option<(Self, Self)> {
-        if self.contains(&val) {
-            Some((self.start..val, val..self.end))
-        } else {
-            None
-        }
+    /// a rule that every part reaching it matches, which isn't a split at all
+    #[test]
+    fn whole_range_matches() {
+        let (workflows, _, categories) =
+            parse("in{x>1000:a,R}\na{x>500:A,R}\n\n{x=1,m=1,a=1,s=1}").unwrap();
+
+        assert_eq!(
+            count_combinations(&workflows, &categories),
+            3000 * 4000 * 4000 * 4000
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
+    #[test]
+    fn other_categories() {
+        let (workflows, _, categories) = parse(
+            "in{u>500000:A,v<1000:R,w>10:a,R}
+a{z<5:A,R}
 
-    use super::RangeSplit;
+{p=1,q=2,u=3,v=4,w=5,z=6}",
+        )
+        .unwrap();
+
+        assert_eq!(categories.names, vec!["p", "q", "u", "v", "w", "z"]);
+
+        let categories = categories.with_bounds(1..=1_000_000).unwrap();
+        let million = 1_000_000u128;
+
+        assert_eq!(
+            count_combinations(&workflows, &categories),
+            500_000 * million.pow(5) + million.pow(2) * 500_000 * 999_001 * 999_990 * 4
+        );
+
+        // a billion ratings in each of six categories is more parts than a
+        // u128 can count
+        let billion = categories.clone().with_bounds(1..=1_000_000_000).unwrap();
+
+        assert_eq!(
+            count_combinations(&workflows, &billion).to_string(),
+            "999500000001999997982000019980000000000000000000000000"
+        );
+        assert!(categories.with_bounds(RangeInclusive::new(5, 4)).is_err());
+
+        let (workflows, _, categories) = parse("in{u>500000000:A,R}\n\n").unwrap();
+        let categories = categories.with_bounds(1..=1_000_000_000).unwrap();
+
+        assert_eq!(categories.names, vec!["u"]);
+        assert_eq!(count_combinations(&workflows, &categories), 500_000_000);
+    }
+
+    #[test]
+    fn bad_parts() {
+        assert!(parse("in{A}\n\n{x=1,m=2}\n{x=3}").is_err());
+        assert!(parse("in{m<3:R,A}\n\n{x=1}").is_err());
+        assert!(parse("in{A}\n\n{x=1,x=2}").is_err());
+        assert!(parse("in{X<3:R,A}\n\n").is_err());
+        assert!(parse("in{A}\n\nx=1").is_err());
+    }
 
     #[test]
     fn range_split() {
-        assert_eq!(None, (1u64..4001).split_lt(4001));
+        let (_, _, categories) = parse("in{x<1:A,R}\n\n").unwrap();
+        let split = |op: &str| {
+            let op = Op::try_from(op).unwrap();
+            let (matching, rest) = RangePart::full(&categories).split(&op);
+            let ranges = |parts: Vec<RangePart>| {
+                parts
+                    .into_iter()
+                    .map(|p| (p.ranges[0].1.start, p.ranges[0].1.end - 1))
+                    .collect::<Vec<_>>()
+            };
+
+            (ranges(matching), ranges(rest))
+        };
 
-        assert_eq!(Some((1..2000, 2000..4001)), (1u64..4001).split_lt(2000));
+        assert_eq!(split("x<4001:A"), (vec![(1, 4000)], vec![]));
 
-        assert_eq!(Some((1..1000, 1000..4001)), (1u64..4001).split_lt(1000));
+        assert_eq!(split("x<2000:A"), (vec![(1, 1999)], vec![(2000, 4000)]));
 
-        assert_eq!(None, (1u64..4001).split_gt(5000));
+        assert_eq!(split("x<1000:A"), (vec![(1, 999)], vec![(1000, 4000)]));
+
+        assert_eq!(split("x>5000:A"), (vec![], vec![(1, 4000)]));
+
+        assert_eq!(split("x>10:A"), (vec![(11, 4000)], vec![(1, 10)]));
+        assert_eq!(split("x>23:A"), (vec![(24, 4000)], vec![(1, 23)]));
+
+        assert_eq!(split("x>=10:A"), (vec![(10, 4000)], vec![(1, 9)]));
+        assert_eq!(split("x<=10:A"), (vec![(1, 10)], vec![(11, 4000)]));
+        assert_eq!(split("x==10:A"), (vec![(10, 10)], vec![(1, 9), (11, 4000)]));
+        assert_eq!(split("x!=10:A"), (vec![(1, 9), (11, 4000)], vec![(10, 10)]));
+        assert_eq!(split("x==5000:A"), (vec![], vec![(1, 4000)]));
+        assert_eq!(split("x!=0:A"), (vec![(1, 4000)], vec![]));
+    }
+
+    #[test]
+    fn parse_conditions() {
+        let op = Op::try_from("x>=10&mm!=5&a==0:qq").unwrap();
+
+        assert!(matches!(op.next(), Next::Workflow("qq")));
+        assert_eq!(
+            op.compares()
+                .iter()
+                .map(|c| (c.category, c.cmp, c.value))
+                .collect::<Vec<_>>(),
+            vec![("x", Cmp::Ge, 10), ("mm", Cmp::Ne, 5), ("a", Cmp::Eq, 0)]
+        );
+        assert_eq!(
+            op.compares()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["x>=10", "mm!=5", "a==0"]
+        );
+
+        for bad in [
+            "x=>10:A",
+            "x>:A",
+            ">10:A",
+            "x>10&:A",
+            "x>10&&m<3:A",
+            "x~10:A",
+        ] {
+            assert!(Op::try_from(bad).is_err(), "{bad}");
+        }
+    }
+
+    /// counts parts with small ratings one at a time, to check the counter
+    /// against the interpreter
+    #[test]
+    fn compound_conditions() {
+        let (workflows, _, categories) = parse(
+            "in{x>10&m<5:A,x!=3&a==2:b,m>=7:c,R}
+b{x<=4&m!=1&a>=1:R,A}
+c{a<=3&x==12:A,a!=8:R,A}
+
+",
+        )
+        .unwrap();
+        let categories = categories.with_bounds(1..=12).unwrap();
+
+        let program = bytecode::Program::compile(&workflows, "in").unwrap();
+        let mut accepted = 0;
+
+        for x in 1..=12 {
+            for m in 1..=12 {
+                for a in 1..=12 {
+                    let part = [("x", x), ("m", m), ("a", a)].into_iter().collect();
+                    let accepts = interpret(&workflows, &part);
+
+                    assert_eq!(program.accepts(&part), accepts);
+                    accepted += accepts as u128;
+                }
+            }
+        }
 
-        assert_eq!(Some((11..4001, 1..11)), (1u64..4001).split_gt(10));
-        assert_eq!(Some((24..4001, 1..24)), (1u64..4001).split_gt(23));
+        assert_eq!(count_combinations(&workflows, &categories), accepted);
     }
 }
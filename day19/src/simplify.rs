@@ -0,0 +1,452 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use crate::{Next, Op, Workflow};
+
+/// rewrites the workflows into fewer, shorter ones that sort every part the
+/// same way, starting from the workflow called `entry`
+///
+/// this keeps going until nothing changes:
+/// - a rule that goes to the same place as the fallthrough straight after it
+///   is dropped
+/// - a workflow that's nothing but a fallthrough is folded into the rules
+///   that go to it
+/// - a workflow that only one fallthrough goes to is pasted in its place
+/// - a workflow that nothing goes to any more is removed
+///
+/// workflows that only send parts round to each other in a loop are left
+/// as they are, see [`crate::analysis::analyze`]
+pub fn simplify<'a>(
+    workflows: &HashMap<&'a str, Workflow<'a>>,
+    entry: &'a str,
+) -> HashMap<&'a str, Workflow<'a>> {
+    let mut workflows = workflows.clone();
+
+    loop {
+        let mut changed = false;
+
+        for workflow in workflows.values_mut() {
+            while let [.., Op::If(_, a), Op::Final(b)] = &workflow.ops[..] {
+                if a != b {
+                    break;
+                }
+
+                workflow.ops.remove(workflow.ops.len() - 2);
+                changed = true;
+            }
+        }
+
+        let folded = workflows
+            .iter()
+            .filter(|(&name, _)| name != entry)
+            .filter_map(|(&name, w)| match w.ops[..] {
+                [Op::Final(next)] => Some((name, next)),
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+
+        // where a chain of folded workflows ends up, or `None` if it goes
+        // round in a loop
+        let fold = |mut name: &'a str| {
+            let mut chain = vec![name];
+
+            loop {
+                match folded.get(name) {
+                    Some(&Next::Workflow(n)) if chain.contains(&n) => return None,
+                    Some(&Next::Workflow(n)) if folded.contains_key(n) => {
+                        chain.push(n);
+                        name = n;
+                    }
+                    Some(&next) => return Some(next),
+                    None => return Some(Next::Workflow(name)),
+                }
+            }
+        };
+
+        for workflow in workflows.values_mut() {
+            for op in &mut workflow.ops {
+                if let Next::Workflow(n) = op.next() {
+                    if let Some(next) = fold(n).filter(|&next| next != op.next()) {
+                        op.set_next(next);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        let mut uses = HashMap::<&str, Vec<(&str, usize)>>::new();
+        for (&name, workflow) in &workflows {
+            for (i, op) in workflow.ops.iter().enumerate() {
+                if let Next::Workflow(n) = op.next() {
+                    uses.entry(n).or_default().push((name, i));
+                }
+            }
+        }
+
+        let unused = workflows
+            .keys()
+            .copied()
+            .filter(|&name| name != entry && !uses.contains_key(name))
+            .collect::<Vec<_>>();
+
+        // removing workflows changes what uses what, so that has to be
+        // worked out again before anything is pasted in
+        if !unused.is_empty() {
+            for name in unused {
+                workflows.remove(name);
+            }
+
+            continue;
+        }
+
+        // only one at a time, since pasting a workflow in moves the rules of
+        // the workflow it's pasted into
+        let inline = uses.iter().find_map(|(&name, from)| match from[..] {
+            [(into, i)] if into != name && name != entry => {
+                matches!(workflows[into].ops[i], Op::Final(_)).then_some((name, into))
+            }
+            _ => None,
+        });
+
+        if let Some((name, into)) = inline {
+            let ops = workflows.remove(name).unwrap().ops;
+            let into = workflows.get_mut(into).unwrap();
+
+            into.ops.pop();
+            into.ops.extend(ops);
+            changed = true;
+        }
+
+        if !changed {
+            return workflows;
+        }
+    }
+}
+
+fn describe(op: &Op) -> String {
+    op.compares()
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// writes the workflows as a graphviz decision diagram, with a node for
+/// every rule that asks something, a yes edge for where it sends parts that
+/// match, and a no edge for the next rule
+pub fn write_dot(
+    workflows: &HashMap<&str, Workflow>,
+    entry: &str,
+    mut out: impl Write,
+) -> io::Result<()> {
+    // where a part ends up going first when it's sent somewhere, which is
+    // the first rule it goes past twice if it's sent round in a loop
+    fn node<'a>(
+        workflows: &HashMap<&str, Workflow<'a>>,
+        mut next: Next<'a>,
+        mut rule: usize,
+    ) -> String {
+        let mut seen = vec![];
+
+        loop {
+            let Next::Workflow(n) = next else {
+                return if next == Next::Accepted { "A" } else { "R" }.to_string();
+            };
+
+            match &workflows[n].ops[rule] {
+                Op::Final(_) if seen.contains(&(n, rule)) => return format!("{n}_{rule}"),
+                Op::Final(to) => {
+                    seen.push((n, rule));
+                    (next, rule) = (*to, 0);
+                }
+                _ => return format!("{n}_{rule}"),
+            }
+        }
+    }
+
+    let mut names = workflows.keys().copied().collect::<Vec<_>>();
+    names.sort_unstable();
+
+    writeln!(out, "digraph workflows {{")?;
+    writeln!(out, "  A [shape=box, color=green];")?;
+    writeln!(out, "  R [shape=box, color=red];")?;
+    writeln!(
+        out,
+        "  start [shape=point];\n  start -> {};",
+        node(workflows, Next::Workflow(entry), 0)
+    )?;
+
+    for name in names {
+        for (i, op) in workflows[name].ops.iter().enumerate() {
+            let here = format!("{name}_{i}");
+
+            if let Op::Final(next) = op {
+                // only a rule that parts go round in a loop gets a node
+                if node(workflows, Next::Workflow(name), i) == here {
+                    writeln!(out, "  {here} [label=\"{name}: loop\"];")?;
+                    writeln!(out, "  {here} -> {};", node(workflows, *next, 0))?;
+                }
+
+                continue;
+            }
+
+            writeln!(out, "  {here} [label=\"{name}: {}\"];", describe(op))?;
+            writeln!(
+                out,
+                "  {here} -> {} [label=yes];",
+                node(workflows, op.next(), 0)
+            )?;
+            writeln!(
+                out,
+                "  {here} -> {} [label=no];",
+                node(workflows, Next::Workflow(name), i + 1)
+            )?;
+        }
+    }
+
+    writeln!(out, "}}")
+}
+
+/// the workflows as nested if/else blocks, pasting in any workflow that's
+/// only gone to from one place and listing the rest separately
+pub fn listing(workflows: &HashMap<&str, Workflow>, entry: &str) -> String {
+    let mut uses = HashMap::<&str, usize>::new();
+    for workflow in workflows.values() {
+        for op in &workflow.ops {
+            if let Next::Workflow(n) = op.next() {
+                *uses.entry(n).or_default() += 1;
+            }
+        }
+    }
+
+    let mut shared = workflows
+        .keys()
+        .copied()
+        .filter(|&name| name != entry && uses.get(name).is_some_and(|&u| u > 1))
+        .collect::<Vec<_>>();
+    shared.sort_unstable();
+
+    let mut out = String::new();
+
+    for name in [entry].into_iter().chain(shared) {
+        out.push_str(&format!("{name}:\n"));
+        block(workflows, &uses, name, 1, &mut out);
+    }
+
+    out
+}
+
+fn block(
+    workflows: &HashMap<&str, Workflow>,
+    uses: &HashMap<&str, usize>,
+    name: &str,
+    depth: usize,
+    out: &mut String,
+) {
+    let indent = "    ".repeat(depth);
+    let ops = &workflows[name].ops;
+
+    let outcome = |next: Next, depth: usize, out: &mut String| match next {
+        Next::Accepted => out.push_str(&format!("{}accept\n", "    ".repeat(depth))),
+        Next::Rejected => out.push_str(&format!("{}reject\n", "    ".repeat(depth))),
+        Next::Workflow(n) if uses.get(n) == Some(&1) => block(workflows, uses, n, depth, out),
+        Next::Workflow(n) => out.push_str(&format!("{}goto {n}\n", "    ".repeat(depth))),
+    };
+
+    for (i, op) in ops.iter().enumerate() {
+        match op {
+            Op::Final(next) if i == 0 => outcome(*next, depth, out),
+            Op::Final(next) => {
+                out.push_str(&format!("{indent}}} else {{\n"));
+                outcome(*next, depth + 1, out);
+                out.push_str(&format!("{indent}}}\n"));
+            }
+            _ => {
+                let keyword = if i == 0 { "" } else { "} else " };
+                out.push_str(&format!("{indent}{keyword}if {} {{\n", describe(op)));
+                outcome(op.next(), depth + 1, out);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shared::rng::XorShift;
+
+    use super::{listing, simplify, write_dot};
+    use crate::{bytecode::Program, count_combinations, parse, tests::SAMPLE, Next, Op, Part};
+
+    #[test]
+    fn sample() {
+        let (workflows, parts, categories) = parse(SAMPLE).unwrap();
+        let simplified = simplify(&workflows, "in");
+
+        // lnx always accepts, so qs folds down to accepting everything, gd
+        // always rejects, and the workflows only one fallthrough goes to get
+        // pasted in
+        let mut names = simplified.keys().copied().collect::<Vec<_>>();
+        names.sort_unstable();
+        assert_eq!(names, vec!["hdj", "in", "px", "qkq"]);
+        assert!(matches!(
+            simplified["in"].ops[..],
+            [
+                Op::If(..),
+                Op::If(_, Next::Accepted),
+                Op::If(..),
+                Op::Final(_)
+            ]
+        ));
+
+        assert_eq!(
+            count_combinations(&simplified, &categories),
+            count_combinations(&workflows, &categories)
+        );
+
+        let before = Program::compile(&workflows, "in").unwrap();
+        let after = Program::compile(&simplified, "in").unwrap();
+        let mut rng = XorShift::default();
+        let mut next = move || rng.in_range(1..=4000);
+
+        let random = (0..10_000).map(|_| {
+            categories
+                .names
+                .iter()
+                .map(|&c| (c, next()))
+                .collect::<Part>()
+        });
+
+        for part in parts.iter().cloned().chain(random) {
+            assert_eq!(before.accepts(&part), after.accepts(&part));
+        }
+    }
+
+    #[test]
+    fn inline_and_list() {
+        let (workflows, ..) = parse("in{x<10:A,b}\nb{m>5:c,R}\nc{a<3:R,A}\n\n").unwrap();
+        let simplified = simplify(&workflows, "in");
+
+        let in_ops = &simplified["in"].ops;
+        assert_eq!(simplified.len(), 2);
+        assert_eq!(in_ops.len(), 3);
+
+        assert_eq!(
+            listing(&simplified, "in"),
+            "in:
+    if x<10 {
+        accept
+    } else if m>5 {
+        if a<3 {
+            reject
+        } else {
+            accept
+        }
+    } else {
+        reject
+    }
+"
+        );
+
+        let mut dot = vec![];
+        write_dot(&simplified, "in", &mut dot).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+
+        assert!(dot.contains("start -> in_0;"));
+        assert!(dot.contains("in_1 -> c_0 [label=yes];"));
+        assert!(dot.contains("in_1 -> R [label=no];"));
+        assert!(dot.contains("c_0 -> A [label=no];"));
+    }
+
+    #[test]
+    fn unreachable_fallthrough() {
+        // u is only ever the fallthrough into v, and nothing goes to u
+        let (workflows, ..) = parse("in{x<10:A,R}\nu{m>5:A,v}\nv{a<3:R,A}\n\n").unwrap();
+        let simplified = simplify(&workflows, "in");
+
+        assert_eq!(simplified.len(), 1);
+        assert_eq!(
+            listing(&simplified, "in"),
+            "in:
+    if x<10 {
+        accept
+    } else {
+        reject
+    }
+"
+        );
+
+        let mut dot = vec![];
+        write_dot(&simplified, "in", &mut dot).unwrap();
+        assert!(String::from_utf8(dot)
+            .unwrap()
+            .contains("in_0 -> R [label=no];"));
+    }
+
+    #[test]
+    fn loops() {
+        // whether in sends anything to the loop
+        for (input, reached) in [
+            ("in{x<5:u,A}\nu{u}\n\n", true),
+            ("in{A}\nu{u}\n\n", false),
+            ("in{x<5:u,A}\nu{v}\nv{u}\n\n", true),
+        ] {
+            let (workflows, ..) = parse(input).unwrap();
+            let simplified = simplify(&workflows, "in");
+
+            let mut dot = vec![];
+            write_dot(&simplified, "in", &mut dot).unwrap();
+            let dot = String::from_utf8(dot).unwrap();
+
+            assert!(dot.contains("u_0 [label=\"u: loop\"];"), "{dot}");
+            assert_eq!(dot.contains("in_0 -> u_0 [label=yes];"), reached, "{dot}");
+        }
+
+        let (workflows, ..) = parse("in{x<5:u,A}\nu{u}\n\n").unwrap();
+        let simplified = simplify(&workflows, "in");
+
+        assert!(matches!(
+            simplified["u"].ops[..],
+            [Op::Final(Next::Workflow("u"))]
+        ));
+        assert_eq!(
+            listing(&simplified, "in"),
+            "in:
+    if x<5 {
+        goto u
+    } else {
+        accept
+    }
+u:
+    goto u
+"
+        );
+    }
+
+    #[test]
+    fn shared_workflows() {
+        let (workflows, ..) = parse("in{x<10:b,m<5:b,A}\nb{a<3:R,A}\n\n").unwrap();
+        let simplified = simplify(&workflows, "in");
+
+        assert_eq!(simplified.len(), 2);
+        assert_eq!(
+            listing(&simplified, "in"),
+            "in:
+    if x<10 {
+        goto b
+    } else if m<5 {
+        goto b
+    } else {
+        accept
+    }
+b:
+    if a<3 {
+        reject
+    } else {
+        accept
+    }
+"
+        );
+    }
+}
@@ -1,3 +1,6 @@
+pub mod polygon;
+pub mod rng;
+
 pub fn char_to_usize(c: char) -> usize {
     ((c as u8) - b'0') as usize
 }
@@ -38,7 +41,7 @@ impl LeastCommonMultiple for $t {
     };
 }
 
-GCD_LCM_Impl!(usize, u64, u32, u16, u8);
+GCD_LCM_Impl!(usize, u64, u32, u16, u8, i64, i128);
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,175 @@
+use crate::GreatestCommonDenominator;
+
+/// a closed polygon on the integer grid, where the last vertex joins back up
+/// with the first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polygon<T> {
+    vertices: Vec<(T, T)>,
+}
+
+impl<T> Polygon<T> {
+    pub fn new(vertices: Vec<(T, T)>) -> Self {
+        Self { vertices }
+    }
+
+    pub fn vertices(&self) -> &[(T, T)] {
+        &self.vertices
+    }
+}
+
+impl<T> FromIterator<(T, T)> for Polygon<T> {
+    fn from_iter<I: IntoIterator<Item = (T, T)>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+macro_rules! polygon_impl {
+    ($($t:ty $(,)?)*) => {
+        $(
+impl Polygon<$t> {
+    /// the polygon traced by starting at the origin and moving `length` steps
+    /// of `step` for each move, like a dig plan
+    pub fn from_moves(moves: impl IntoIterator<Item = (($t, $t), $t)>) -> Self {
+        moves
+            .into_iter()
+            .scan((0, 0), |curr, (step, length)| {
+                curr.0 += step.0 * length;
+                curr.1 += step.1 * length;
+
+                Some(*curr)
+            })
+            .collect()
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (($t, $t), ($t, $t))> + '_ {
+        self.vertices
+            .iter()
+            .copied()
+            .zip(self.vertices.iter().copied().cycle().skip(1))
+    }
+
+    /// whether every edge is horizontal or vertical
+    pub fn is_orthogonal(&self) -> bool {
+        self.edges().all(|(a, b)| a.0 == b.0 || a.1 == b.1)
+    }
+
+    /// twice the area inside the polygon, by the shoelace formula, which is
+    /// always a whole number for vertices on the grid
+    pub fn twice_area(&self) -> $t {
+        self.edges()
+            .map(|(a, b)| a.0 * b.1 - a.1 * b.0)
+            .sum::<$t>()
+            .abs()
+    }
+
+    /// the number of grid points on the edges of the polygon
+    pub fn boundary_points(&self) -> $t {
+        self.edges()
+            .map(|(a, b)| (b.0 - a.0).abs().gcd(&(b.1 - a.1).abs()))
+            .sum()
+    }
+
+    /// the number of grid points strictly inside the polygon, by pick's
+    /// theorem, which is none at all for a polygon with no area
+    pub fn interior_points(&self) -> $t {
+        match self.twice_area() {
+            0 => 0,
+            twice_area => (twice_area - self.boundary_points()) / 2 + 1,
+        }
+    }
+
+    /// the number of unit squares dug out when a one metre trench is dug
+    /// along the edges, and everything inside it is dug out too
+    ///
+    /// a trench that only doubles back on itself still digs out the squares
+    /// it goes through, so this leans on pick's theorem even with no area
+    pub fn trench_area(&self) -> $t {
+        if self.vertices.is_empty() {
+            return 0;
+        }
+
+        (self.twice_area() + self.boundary_points()) / 2 + 1
+    }
+}
+        )*
+    };
+}
+
+polygon_impl!(i64, i128);
+
+#[cfg(test)]
+mod tests {
+    use super::Polygon;
+
+    #[test]
+    fn square() {
+        let square = Polygon::new(vec![(0i64, 0), (0, 4), (4, 4), (4, 0)]);
+
+        assert!(square.is_orthogonal());
+        assert_eq!(square.twice_area(), 32);
+        assert_eq!(square.boundary_points(), 16);
+        assert_eq!(square.interior_points(), 9);
+        assert_eq!(square.trench_area(), 25);
+    }
+
+    #[test]
+    fn triangle() {
+        let triangle = Polygon::new(vec![(0i128, 0), (4, 2), (0, 6)]);
+
+        assert!(!triangle.is_orthogonal());
+        assert_eq!(triangle.twice_area(), 24);
+        assert_eq!(triangle.boundary_points(), 2 + 4 + 6);
+        assert_eq!(triangle.interior_points(), 7);
+    }
+
+    #[test]
+    fn degenerate() {
+        let empty = Polygon::<i64>::new(vec![]);
+
+        assert_eq!(empty.twice_area(), 0);
+        assert_eq!(empty.interior_points(), 0);
+        assert_eq!(empty.trench_area(), 0);
+
+        let point = Polygon::new(vec![(3i64, 3)]);
+
+        assert_eq!(point.interior_points(), 0);
+        assert_eq!(point.trench_area(), 1);
+
+        // out three and straight back
+        let line = Polygon::<i128>::from_moves([((0, 1), 3), ((0, -1), 3)]);
+
+        assert_eq!(line.twice_area(), 0);
+        assert_eq!(line.boundary_points(), 6);
+        assert_eq!(line.interior_points(), 0);
+        assert_eq!(line.trench_area(), 4);
+    }
+
+    #[test]
+    fn dig_plan() {
+        let (r, d, l, u) = ((0, 1), (1, 0), (0, -1), (-1, 0));
+        let plan = Polygon::<i64>::from_moves([
+            (r, 6),
+            (d, 5),
+            (l, 2),
+            (d, 2),
+            (r, 2),
+            (d, 2),
+            (l, 5),
+            (u, 2),
+            (l, 1),
+            (u, 2),
+            (r, 2),
+            (u, 3),
+            (l, 2),
+            (u, 2),
+        ]);
+
+        assert!(plan.is_orthogonal());
+        assert_eq!(plan.trench_area(), 62);
+
+        // the same plan clockwise and anticlockwise
+        let mut reversed = plan.vertices().to_vec();
+        reversed.reverse();
+        assert_eq!(Polygon::new(reversed).trench_area(), 62);
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::BuildHasher,
+    ops::RangeInclusive,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// a small xorshift generator, which is plenty for random test inputs and
+/// picking things at random, and gives the same numbers for the same seed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XorShift {
+    state: u64,
+}
+
+impl XorShift {
+    /// the seed for when any seed will do
+    pub const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+    /// xorshift never gets away from zero, so that seed is swapped for
+    /// [`XorShift::SEED`]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: if seed == 0 { Self::SEED } else { seed },
+        }
+    }
+
+    /// seeded differently on every run, from the clock and the standard
+    /// library's random hash keys
+    pub fn from_entropy() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos());
+
+        Self::new(RandomState::new().hash_one(nanos))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    pub fn next_u128(&mut self) -> u128 {
+        ((self.next_u64() as u128) << 64) | self.next_u64() as u128
+    }
+
+    /// a number in `range`, leaning very slightly towards the start of it
+    /// unless its length is a power of two
+    pub fn in_range(&mut self, range: RangeInclusive<u64>) -> u64 {
+        let (start, end) = (*range.start(), *range.end());
+
+        match (end - start).checked_add(1) {
+            Some(len) => start + self.next_u64() % len,
+            None => self.next_u64(),
+        }
+    }
+}
+
+impl Default for XorShift {
+    fn default() -> Self {
+        Self::new(Self::SEED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XorShift;
+
+    #[test]
+    fn xorshift() {
+        let mut a = XorShift::default();
+        let mut b = XorShift::new(XorShift::SEED);
+
+        assert!((0..100).all(|_| a.next_u64() == b.next_u64()));
+        assert_ne!(XorShift::new(0).next_u64(), 0);
+
+        for _ in 0..1000 {
+            assert!((5..=9).contains(&a.in_range(5..=9)));
+        }
+
+        assert_eq!(a.in_range(7..=7), 7);
+        a.in_range(0..=u64::MAX);
+    }
+}